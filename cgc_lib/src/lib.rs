@@ -3,4 +3,4 @@ pub mod simple_menu;
 pub mod utils;
 
 pub use common_assets::{CGCCommonAssetsPlugin, COMMON_FONT_NAME};
-pub use utils::despawn_entities;
+pub use utils::{despawn_entities, wrap_val};
@@ -5,7 +5,7 @@ use rand::Rng;
 
 use crate::utils::direction::Direction;
 use crate::utils::maze::{Maze, MazeCell};
-use crate::utils::wrapper::wrap_val;
+use cgc_lib::wrap_val;
 
 #[derive(Clone, Copy, Debug)]
 pub enum PipeType {
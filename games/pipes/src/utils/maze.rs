@@ -1,7 +1,9 @@
 use bevy::utils::HashSet;
 use rand::{seq::SliceRandom, Rng};
 
-use super::{direction::Direction, wrapper::wrap_val};
+use cgc_lib::wrap_val;
+
+use super::direction::Direction;
 
 #[derive(Clone, Debug, Default)]
 pub struct MazeCell {
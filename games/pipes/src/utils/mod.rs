@@ -1,4 +1,3 @@
-pub mod wrapper;
 pub mod components;
 pub mod direction;
 pub mod maze;
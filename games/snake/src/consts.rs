@@ -2,5 +2,49 @@ use bevy::prelude::*;
 
 pub const BACKGROUND_COL: Color = Color::BLACK;
 pub const FOREGROUND_COL: Color = Color::srgb(0.0, 128.0, 0.0);
+// Colors of the first and the second snake.
+pub const PLAYER_COLS: [Color; MAX_PLAYERS] = [FOREGROUND_COL, Color::srgb(0.85, 0.85, 0.85)];
+pub const BORDER_COL: Color = Color::srgb(0.0, 0.4, 0.0);
+// Border of the wrap-around arena, which the snake passes through.
+pub const WRAP_BORDER_COL: Color = Color::srgb(0.0, 0.15, 0.0);
+// Tick duration of the normal difficulty.
 pub const TICK_DURATION: f64 = 0.1;
+// With the speed up on, the tick gets shorter by the factor
+// every time the score grows by the step.
+pub const SPEED_UP_STEP: usize = 5;
+pub const SPEED_UP_FACTOR: f64 = 0.9;
+pub const MIN_TICK_DURATION: f64 = 0.03;
 pub const BLOCK_SIZE: f32 = 20.0;
+// Snakes of the local multiplayer game.
+pub const MAX_PLAYERS: usize = 2;
+// Turns remembered ahead of the snake, one is applied every tick.
+pub const INPUT_QUEUE_SIZE: usize = 3;
+// Special food appears every interval,
+// while there is less of it than the maximum.
+pub const SPECIAL_FOOD_INTERVAL: f64 = 7.0;
+pub const MAX_SPECIAL_FOOD: usize = 2;
+// Time before uneaten special food disappears.
+pub const BONUS_FOOD_LIFETIME: f64 = 5.0;
+pub const SPECIAL_FOOD_LIFETIME: f64 = 10.0;
+// Special food blinks during the last seconds of its lifetime.
+pub const FOOD_BLINK_TIME: f64 = 1.5;
+pub const BONUS_FOOD_POINTS: usize = 5;
+// Segments a shrink pill takes from the snake.
+pub const SHRINK_SEGMENTS: usize = 3;
+// Speed items multiply the tick duration for a while.
+pub const SPEED_EFFECT_DURATION: f64 = 5.0;
+pub const SPEED_UP_EFFECT: f64 = 0.6;
+pub const SLOW_DOWN_EFFECT: f64 = 1.6;
+pub const BONUS_FOOD_COL: Color = Color::srgb(1.0, 0.85, 0.0);
+pub const SHRINK_PILL_COL: Color = Color::srgb(0.8, 0.2, 0.9);
+pub const SPEED_UP_PILL_COL: Color = Color::srgb(1.0, 0.3, 0.1);
+pub const SLOW_DOWN_PILL_COL: Color = Color::srgb(0.2, 0.6, 1.0);
+// Size of the arena in blocks.
+pub const ARENA_WIDTH: i32 = 38;
+pub const ARENA_HEIGHT: i32 = 38;
+// Obstacle maps, see `Arena` for the format.
+pub const MAPS: [(&str, &str); 3] = [
+    ("CORNERS", include_str!("maps/corners.txt")),
+    ("ROOMS", include_str!("maps/rooms.txt")),
+    ("PILLARS", include_str!("maps/pillars.txt")),
+];
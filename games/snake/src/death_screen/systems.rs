@@ -2,13 +2,38 @@ use bevy::prelude::*;
 
 use crate::{
     consts,
-    state::{GameState, Score},
+    state::{Difficulty, GameState, Players, Score, SpeedUp, Winner},
     utils::components::HasFocus,
 };
 
 use super::components::{ButtonAction, OnDeathScreen};
 
-pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>, score: Res<Score>) {
+pub fn render_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    speed_up: Res<SpeedUp>,
+    players: Res<Players>,
+    winner: Res<Winner>,
+) {
+    let title = match (players.0, winner.0) {
+        (1, _) => String::from("GAME OVER"),
+        (_, Some(winner)) => format!("PLAYER {} WINS", winner + 1),
+        (_, None) => String::from("DRAW"),
+    };
+    let scores = if players.0 == 1 {
+        vec![format!("YOUR SCORE: {}", score.0[0])]
+    } else {
+        (0..players.0)
+            .map(|player| format!("PLAYER {}: {}", player + 1, score.0[player]))
+            .collect()
+    };
+    let difficulty = if speed_up.0 {
+        format!("DIFFICULTY: {} + SPEED UP", *difficulty)
+    } else {
+        format!("DIFFICULTY: {}", *difficulty)
+    };
     let text_style = TextStyle {
         font_size: 16.,
         font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
@@ -35,7 +60,7 @@ pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>, sco
         .with_children(|parent| {
             parent.spawn(TextBundle {
                 text: Text::from_section(
-                    "GAME OVER",
+                    title,
                     TextStyle {
                         font_size: 16.,
                         ..text_style.clone()
@@ -43,14 +68,20 @@ pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>, sco
                 ),
                 ..default()
             });
+            for score in scores {
+                parent.spawn(TextBundle {
+                    text: Text::from_section(
+                        score,
+                        TextStyle {
+                            font_size: 16.,
+                            ..text_style.clone()
+                        },
+                    ),
+                    ..default()
+                });
+            }
             parent.spawn(TextBundle {
-                text: Text::from_section(
-                    format!("YOUR SCORE: {}", score.0),
-                    TextStyle {
-                        font_size: 16.,
-                        ..text_style.clone()
-                    },
-                ),
+                text: Text::from_section(difficulty, text_style.clone()),
                 ..default()
             });
             parent
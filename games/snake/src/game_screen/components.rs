@@ -1,6 +1,10 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+use crate::consts;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Direction {
     Up,
     Down,
@@ -8,23 +12,126 @@ pub enum Direction {
     Right,
 }
 
+impl Direction {
+    pub fn opposite(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
 #[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct VirtualPosition {
     pub x: i32,
     pub y: i32,
 }
 
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Food {
+    // Always in the arena, moves to a new place when eaten.
+    Regular,
+    // Worth extra points, but doesn't stay for long.
+    Bonus,
+    // Makes the snake shorter.
+    Shrink,
+    // Change the speed of the snake for a while.
+    SpeedUp,
+    SlowDown,
+}
+
+impl Food {
+    /// Food which appears from time to time next to the regular one.
+    pub fn special() -> [Food; 4] {
+        [Food::Bonus, Food::Shrink, Food::SpeedUp, Food::SlowDown]
+    }
+
+    pub fn points(&self) -> usize {
+        match self {
+            Food::Regular | Food::SpeedUp => 1,
+            Food::Bonus => consts::BONUS_FOOD_POINTS,
+            Food::Shrink | Food::SlowDown => 0,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Food::Regular => consts::FOREGROUND_COL,
+            Food::Bonus => consts::BONUS_FOOD_COL,
+            Food::Shrink => consts::SHRINK_PILL_COL,
+            Food::SpeedUp => consts::SPEED_UP_PILL_COL,
+            Food::SlowDown => consts::SLOW_DOWN_PILL_COL,
+        }
+    }
+
+    /// Pills are smaller than food.
+    pub fn size(&self) -> f32 {
+        match self {
+            Food::Regular | Food::Bonus => consts::BLOCK_SIZE,
+            Food::Shrink | Food::SpeedUp | Food::SlowDown => consts::BLOCK_SIZE * 0.6,
+        }
+    }
+
+    /// Time before the food disappears, regular food stays until eaten.
+    pub fn lifetime(&self) -> Option<Duration> {
+        match self {
+            Food::Regular => None,
+            Food::Bonus => Some(Duration::from_secs_f64(consts::BONUS_FOOD_LIFETIME)),
+            Food::Shrink | Food::SpeedUp | Food::SlowDown => {
+                Some(Duration::from_secs_f64(consts::SPECIAL_FOOD_LIFETIME))
+            }
+        }
+    }
+}
+
+/// Time left before special food disappears.
+#[derive(Component, Deref, DerefMut)]
+pub struct FoodTimer(pub Timer);
+
+/// Time before the next move of the snake,
+/// every snake moves at its own speed.
+#[derive(Component, Deref, DerefMut)]
+pub struct MoveTimer(pub Timer);
+
+/// Change of the speed after the snake eats a speed item,
+/// it lasts until the timer finishes.
 #[derive(Component)]
-pub struct Food;
+pub struct SpeedEffect {
+    // Multiplier of the tick duration.
+    pub factor: f64,
+    pub timer: Timer,
+}
+
+impl Default for SpeedEffect {
+    fn default() -> Self {
+        Self {
+            factor: 1.,
+            timer: Timer::from_seconds(consts::SPEED_EFFECT_DURATION as f32, TimerMode::Once),
+        }
+    }
+}
+
+impl SpeedEffect {
+    pub fn start(&mut self, factor: f64) {
+        self.factor = factor;
+        self.timer.reset();
+    }
+}
 
+/// Index of the player, it's on the snake and on the sprites of its segments.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Player(pub usize);
+
+/// Sprite of a segment of the snake, the head has index 0.
 #[derive(Component)]
-pub struct SnakePart;
+pub struct SnakePart(pub usize);
 
+/// Wall drawn around the arena or an obstacle inside it.
 #[derive(Component)]
-pub struct SnakeHead {
-    pub locked: bool,
-    pub direction: Direction,
-}
+pub struct Wall;
 
+/// Current speed of the snakes shown above the arena.
 #[derive(Component)]
-pub struct SnakeTail;
+pub struct SpeedText;
@@ -1,7 +1,16 @@
+use super::components::Food;
 use bevy::prelude::*;
 
 #[derive(Debug, Event)]
-pub struct EatingEvent;
+pub struct EatingEvent {
+    pub player: usize,
+    // Entity of the eaten food.
+    pub entity: Entity,
+    pub food: Food,
+}
 
+/// Snake of the player has crashed.
 #[derive(Debug, Event)]
-pub struct GameOverEvent;
+pub struct GameOverEvent {
+    pub player: usize,
+}
@@ -1,14 +1,17 @@
 use std::time::Duration;
 
-use crate::{consts, state::GameState};
+use crate::{consts, state::GameState, utils::systems::despawn_screen};
 use bevy::prelude::*;
 use events::{EatingEvent, GameOverEvent};
-use resources::MainTimer;
+use resources::{Arena, SpecialFoodTimer};
 
 mod components;
 mod events;
 mod resources;
+mod snake;
 mod systems;
+#[cfg(test)]
+mod tests;
 
 pub struct GameScreenPlugin;
 
@@ -16,34 +19,54 @@ impl Plugin for GameScreenPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<EatingEvent>()
             .add_event::<GameOverEvent>()
-            .insert_resource(MainTimer(Timer::new(
-                Duration::from_secs_f64(consts::TICK_DURATION),
+            .init_resource::<Arena>()
+            .insert_resource(SpecialFoodTimer(Timer::new(
+                Duration::from_secs_f64(consts::SPECIAL_FOOD_INTERVAL),
                 TimerMode::Repeating,
             )))
             .add_systems(
                 OnEnter(GameState::Playing),
                 (
-                    systems::spawn_food,
-                    systems::spawn_snake,
-                    systems::reset_score,
-                    systems::reset_timer,
-                ),
+                    systems::setup_arena,
+                    (
+                        systems::spawn_snake,
+                        systems::reset_score,
+                        systems::reset_timer,
+                        systems::spawn_walls,
+                        systems::fit_arena_to_window,
+                    ),
+                    // Food is placed after the snakes, so it doesn't appear on them.
+                    (systems::spawn_food, systems::spawn_hud),
+                )
+                    .chain(),
             )
             .add_systems(
                 Update,
                 (
                     systems::handle_input,
-                    systems::check_on_food,
-                    systems::check_on_cannibalism,
-                    systems::check_on_edge_running,
                     systems::move_snake,
+                    systems::render_snake.after(systems::move_snake),
                     systems::position_translation,
-                    systems::eat_food.run_if(on_event::<events::EatingEvent>()),
-                    systems::grow_snake.run_if(on_event::<events::EatingEvent>()),
+                    systems::spawn_special_food,
+                    // Eaten food can't expire in the same frame.
+                    systems::expire_food.after(systems::eat_food),
+                    systems::eat_food
+                        .after(systems::move_snake)
+                        .run_if(on_event::<events::EatingEvent>()),
+                    systems::grow_snake
+                        .after(systems::move_snake)
+                        .run_if(on_event::<events::EatingEvent>()),
+                    systems::update_speed.after(systems::eat_food),
                     systems::game_over.run_if(on_event::<events::GameOverEvent>()),
                 )
                     .distributive_run_if(in_state(GameState::Playing)),
             )
-            .add_systems(OnExit(GameState::Playing), systems::despawn_snake);
+            .add_systems(
+                OnExit(GameState::Playing),
+                (
+                    systems::despawn_snake,
+                    despawn_screen::<components::SpeedText>,
+                ),
+            );
     }
 }
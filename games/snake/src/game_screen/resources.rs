@@ -1,4 +1,174 @@
-use bevy::prelude::*;
+use std::str::FromStr;
 
+use bevy::{prelude::*, utils::HashSet};
+use rand::{seq::IteratorRandom, Rng};
+
+use cgc_lib::wrap_val;
+
+use crate::{consts, state::ArenaMode};
+
+use super::components::{Direction, VirtualPosition};
+
+/// Time before the next special food appears.
 #[derive(Resource, Deref, DerefMut)]
-pub struct MainTimer(pub Timer);
+pub struct SpecialFoodTimer(pub Timer);
+
+/// Playfield in blocks, which doesn't depend on the window size.
+///
+/// Positions inside the arena go from (0, 0) in the bottom left
+/// corner to (width - 1, height - 1) in the top right one.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct Arena {
+    pub width: i32,
+    pub height: i32,
+    // The snake goes through the border instead of crashing into it.
+    pub wrap: bool,
+    pub obstacles: HashSet<VirtualPosition>,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self {
+            width: consts::ARENA_WIDTH,
+            height: consts::ARENA_HEIGHT,
+            wrap: false,
+            obstacles: HashSet::new(),
+        }
+    }
+}
+
+impl Arena {
+    pub fn from_mode(mode: ArenaMode) -> Self {
+        match mode {
+            ArenaMode::Walls => Arena::default(),
+            ArenaMode::Wrap => Arena {
+                wrap: true,
+                ..default()
+            },
+            // Maps are a part of the game, so they must be valid.
+            ArenaMode::Map(index) => consts::MAPS[index]
+                .1
+                .parse()
+                .unwrap_or_else(|err| panic!("Invalid map {}: {err}", consts::MAPS[index].0)),
+        }
+    }
+
+    pub fn contains(&self, pos: &VirtualPosition) -> bool {
+        (0..self.width).contains(&pos.x) && (0..self.height).contains(&pos.y)
+    }
+
+    /// Position is inside the arena and not taken by an obstacle.
+    pub fn is_free(&self, pos: &VirtualPosition) -> bool {
+        self.contains(pos) && !self.obstacles.contains(pos)
+    }
+
+    /// Where the snake of the player starts and where it heads.
+    /// In the game for two, the snakes start on parallel rows
+    /// heading in the opposite directions.
+    pub fn start(&self, player: usize, players: usize) -> (VirtualPosition, Direction) {
+        let center = self.center();
+        match (players, player) {
+            (1, _) => (center, Direction::Right),
+            (_, 0) => (
+                VirtualPosition {
+                    x: center.x,
+                    y: center.y - 2,
+                },
+                Direction::Right,
+            ),
+            _ => (
+                VirtualPosition {
+                    x: center.x - 1,
+                    y: center.y + 2,
+                },
+                Direction::Left,
+            ),
+        }
+    }
+
+    /// Random free position, which isn't taken by anything else.
+    /// There is none, when the whole arena is taken.
+    pub fn random_free_cell<R: Rng>(
+        &self,
+        rng: &mut R,
+        is_taken: impl Fn(&VirtualPosition) -> bool,
+    ) -> Option<VirtualPosition> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| VirtualPosition { x, y }))
+            .filter(|pos| self.is_free(pos) && !is_taken(pos))
+            .choose(rng)
+    }
+
+    /// Brings a position which has just left the arena
+    /// back from the opposite side, if the arena wraps.
+    pub fn wrap(&self, pos: VirtualPosition) -> VirtualPosition {
+        VirtualPosition {
+            x: wrap_val(self.wrap, pos.x, 0, self.width - 1),
+            y: wrap_val(self.wrap, pos.y, 0, self.height - 1),
+        }
+    }
+
+    pub fn center(&self) -> VirtualPosition {
+        VirtualPosition {
+            x: self.width / 2,
+            y: self.height / 2,
+        }
+    }
+
+    /// Translation of the block center, the arena is centered on the screen.
+    pub fn translation(&self, pos: &VirtualPosition) -> Vec3 {
+        Vec3::new(
+            (pos.x as f32 + 0.5 - self.width as f32 / 2.) * consts::BLOCK_SIZE,
+            (pos.y as f32 + 0.5 - self.height as f32 / 2.) * consts::BLOCK_SIZE,
+            0.0,
+        )
+    }
+}
+
+/// Maps are stored as text, every line is a row of the arena
+/// from top to bottom. `#` is an obstacle and `.` is a free cell:
+///
+/// ```text
+/// ..........
+/// ..####....
+/// ..........
+/// ```
+impl FromStr for Arena {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+        let width = rows.first().map_or(0, |row| row.len());
+        if width == 0 {
+            return Err(String::from("Map is empty"));
+        }
+        let height = rows.len() as i32;
+        let mut obstacles = HashSet::new();
+        for (row_index, row) in rows.iter().enumerate() {
+            if row.len() != width {
+                return Err(format!("Rows should have the same length: {row}"));
+            }
+            // First row is the top of the arena.
+            let y = height - 1 - row_index as i32;
+            for (x, tile) in row.chars().enumerate() {
+                match tile {
+                    '#' => {
+                        obstacles.insert(VirtualPosition { x: x as i32, y });
+                    }
+                    '.' => {}
+                    _ => return Err(format!("Unknown tile: {tile}")),
+                }
+            }
+        }
+        Ok(Arena {
+            width: width as i32,
+            height,
+            wrap: false,
+            obstacles,
+        })
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::consts;
+
+use super::{
+    components::{Direction, VirtualPosition},
+    resources::Arena,
+};
+
+/// Outcome of a single move of the snake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Moved,
+    // The snake has run into a wall, an obstacle or itself.
+    // It stays where it was.
+    Crashed,
+}
+
+/// Snake model, the sprites of its segments are only a view of it.
+///
+/// The body goes from the head to the end of the tail,
+/// and the grid tells which cells of the arena the body takes.
+#[derive(Component, Debug, Clone)]
+pub struct Snake {
+    body: VecDeque<VirtualPosition>,
+    grid: Vec<bool>,
+    width: i32,
+    direction: Direction,
+    // Turns pressed faster than the snake moves,
+    // the first one is applied on the next move.
+    turns: VecDeque<Direction>,
+    // Segments to add, one is added per move.
+    growth: usize,
+}
+
+impl Snake {
+    /// Snake of a single segment at the given position.
+    pub fn new(arena: &Arena, head: VirtualPosition, direction: Direction) -> Self {
+        let mut snake = Self {
+            body: VecDeque::new(),
+            grid: vec![false; (arena.width * arena.height) as usize],
+            width: arena.width,
+            direction,
+            turns: VecDeque::with_capacity(consts::INPUT_QUEUE_SIZE),
+            growth: 0,
+        };
+        snake.body.push_back(head);
+        snake.set_occupied(head, true);
+        snake
+    }
+
+    pub fn head(&self) -> VirtualPosition {
+        self.body[0]
+    }
+
+    pub fn segment(&self, index: usize) -> Option<VirtualPosition> {
+        self.body.get(index).copied()
+    }
+
+    pub fn body(&self) -> impl Iterator<Item = &VirtualPosition> {
+        self.body.iter()
+    }
+
+    /// Position is taken by the body of the snake.
+    pub fn occupies(&self, pos: &VirtualPosition) -> bool {
+        self.index(pos).is_some_and(|index| self.grid[index])
+    }
+
+    /// Queues a turn, unless the queue is full or the turn
+    /// doesn't change the direction the snake will have by then.
+    /// Reversing into the neck is never allowed.
+    pub fn queue_turn(&mut self, turn: Direction) -> bool {
+        let direction = self.turns.back().copied().unwrap_or(self.direction);
+        if self.turns.len() >= consts::INPUT_QUEUE_SIZE
+            || turn == direction
+            || turn == direction.opposite()
+        {
+            return false;
+        }
+        self.turns.push_back(turn);
+        true
+    }
+
+    /// Makes the snake longer by the given number of segments.
+    /// They are added at the end of the tail during the next moves.
+    pub fn grow(&mut self, segments: usize) {
+        self.growth += segments;
+    }
+
+    /// Takes segments from the end of the tail,
+    /// the head always stays.
+    pub fn shrink(&mut self, segments: usize) {
+        let from_growth = segments.min(self.growth);
+        self.growth -= from_growth;
+        for _ in from_growth..segments {
+            if self.body.len() == 1 {
+                break;
+            }
+            if let Some(tail) = self.body.pop_back() {
+                self.set_occupied(tail, false);
+            }
+        }
+    }
+
+    /// Position the head moves to, after the next queued turn.
+    pub fn next_head(&self, arena: &Arena) -> VirtualPosition {
+        let direction = self.turns.front().copied().unwrap_or(self.direction);
+        let head = self.head();
+        let (dx, dy) = match direction {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        };
+        arena.wrap(VirtualPosition {
+            x: head.x + dx,
+            y: head.y + dy,
+        })
+    }
+
+    /// The snake runs into another one on its next move,
+    /// either into its body or head to head, if the other one moves as well.
+    ///
+    /// The end of the tail of the other snake counts as its body,
+    /// even if it leaves the cell during the move.
+    pub fn crashes_into(&self, other: &Snake, other_moves: bool, arena: &Arena) -> bool {
+        let next = self.next_head(arena);
+        other.occupies(&next) || (other_moves && next == other.next_head(arena))
+    }
+
+    /// Moves the snake one cell forward, applying the next queued turn.
+    pub fn step(&mut self, arena: &Arena) -> Step {
+        let next = self.next_head(arena);
+        if let Some(turn) = self.turns.pop_front() {
+            self.direction = turn;
+        }
+        if !arena.is_free(&next) {
+            return Step::Crashed;
+        }
+        // The end of the tail leaves its cell during this move,
+        // unless the snake is growing.
+        let tail = *self.body.back().unwrap();
+        let tail_leaves = self.growth == 0;
+        if self.occupies(&next) && !(tail_leaves && next == tail) {
+            return Step::Crashed;
+        }
+        if tail_leaves {
+            self.body.pop_back();
+            self.set_occupied(tail, false);
+        } else {
+            self.growth -= 1;
+        }
+        self.body.push_front(next);
+        self.set_occupied(next, true);
+        Step::Moved
+    }
+
+    fn index(&self, pos: &VirtualPosition) -> Option<usize> {
+        let height = self.grid.len() as i32 / self.width;
+        if (0..self.width).contains(&pos.x) && (0..height).contains(&pos.y) {
+            Some((pos.y * self.width + pos.x) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn set_occupied(&mut self, pos: VirtualPosition, occupied: bool) {
+        if let Some(index) = self.index(&pos) {
+            self.grid[index] = occupied;
+        }
+    }
+}
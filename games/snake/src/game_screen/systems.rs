@@ -1,273 +1,509 @@
-use bevy::{prelude::*, utils::HashSet};
-use rand::Rng;
+use bevy::{prelude::*, render::camera::ScalingMode};
+use rand::seq::SliceRandom;
 
 use crate::{
     consts::{self, BLOCK_SIZE, FOREGROUND_COL},
-    state::{GameState, Score},
+    state::{ArenaMode, Difficulty, GameState, Players, Score, SpeedUp, Winner},
 };
 
 use super::{
-    components::{Direction, Food, SnakeHead, SnakePart, SnakeTail, VirtualPosition},
+    components::{
+        Direction, Food, FoodTimer, MoveTimer, Player, SnakePart, SpeedEffect, SpeedText,
+        VirtualPosition, Wall,
+    },
     events::{EatingEvent, GameOverEvent},
-    resources::MainTimer,
+    resources::{Arena, SpecialFoodTimer},
+    snake::{Snake, Step},
 };
 
 pub fn reset_score(mut score: ResMut<Score>) {
-    score.0 = 0;
+    *score = Score::default();
 }
 
-pub fn reset_timer(mut timer: ResMut<MainTimer>) {
-    timer.reset();
+pub fn reset_timer(mut special_food_timer: ResMut<SpecialFoodTimer>) {
+    special_food_timer.reset();
 }
 
-pub fn spawn_snake(mut commands: Commands) {
+/// Shortens the tick of every snake as the score of its player grows,
+/// if the speed up is on, and applies the effect of speed items
+/// the snake has eaten while it lasts.
+pub fn update_speed(
+    time: Res<Time>,
+    score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    speed_up: Res<SpeedUp>,
+    mut snake_query: Query<(&Player, &mut MoveTimer, &mut SpeedEffect)>,
+    mut speed_text_query: Query<&mut Text, With<SpeedText>>,
+) {
+    let mut changed = false;
+    for (player, mut timer, mut speed_effect) in &mut snake_query {
+        if speed_effect.timer.tick(time.delta()).just_finished() {
+            speed_effect.factor = 1.;
+        }
+        let duration = difficulty
+            .tick_duration(score.0[player.0], speed_up.0)
+            .mul_f64(speed_effect.factor);
+        if timer.duration() != duration {
+            timer.set_duration(duration);
+            changed = true;
+        }
+    }
+    if !changed {
+        return;
+    }
+    let label = speed_label(snake_query.iter().map(|(player, timer, _)| (player, timer)));
+    for mut text in &mut speed_text_query {
+        text.sections[0].value = label.clone();
+    }
+}
+
+/// Speed of every snake in blocks per second, in the order of the players.
+fn speed_label<'a>(timers: impl Iterator<Item = (&'a Player, &'a MoveTimer)>) -> String {
+    let mut timers = timers.collect::<Vec<_>>();
+    timers.sort_by_key(|(player, _)| player.0);
+    let speeds = timers
+        .iter()
+        .map(|(_, timer)| format!("{:.1}", 1. / timer.duration().as_secs_f32()))
+        .collect::<Vec<_>>();
+    format!("SPEED: {}", speeds.join(" / "))
+}
+
+pub fn spawn_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    snake_query: Query<(&Player, &MoveTimer)>,
+) {
     commands.spawn((
-        SpriteBundle {
-            sprite: Sprite {
-                color: FOREGROUND_COL,
-                custom_size: Some(Vec2::new(BLOCK_SIZE, BLOCK_SIZE)),
+        TextBundle {
+            text: Text::from_section(
+                speed_label(snake_query.iter()),
+                TextStyle {
+                    font_size: 12.,
+                    font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
+                    color: FOREGROUND_COL,
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(4.),
+                left: Val::Px(4.),
                 ..default()
             },
             ..default()
         },
-        VirtualPosition { x: 0, y: 0 },
-        SnakePart,
-        SnakeHead {
-            direction: Direction::Right,
-            locked: false,
-        },
+        SpeedText,
     ));
-    commands.spawn((VirtualPosition { x: -1, y: 0 }, SnakeTail));
 }
 
-pub fn spawn_food(mut commands: Commands) {
-    commands.spawn((
+pub fn setup_arena(mode: Res<ArenaMode>, mut arena: ResMut<Arena>) {
+    *arena = Arena::from_mode(*mode);
+}
+
+/// Scales the camera, so the whole arena with its border
+/// fits the window of any size.
+pub fn fit_arena_to_window(
+    arena: Res<Arena>,
+    mut projection_query: Query<&mut OrthographicProjection>,
+) {
+    for mut projection in &mut projection_query {
+        projection.scaling_mode = ScalingMode::AutoMin {
+            min_width: (arena.width + 2) as f32 * BLOCK_SIZE,
+            min_height: (arena.height + 2) as f32 * BLOCK_SIZE,
+        };
+    }
+}
+
+/// Draws a wall one block thick around the arena
+/// and the obstacles inside it.
+pub fn spawn_walls(mut commands: Commands, arena: Res<Arena>) {
+    let width = arena.width as f32 * BLOCK_SIZE;
+    let height = arena.height as f32 * BLOCK_SIZE;
+    let sides = [
+        // Top and bottom walls cover the corners.
+        (
+            Vec2::new(0., (height + BLOCK_SIZE) / 2.),
+            Vec2::new(width + BLOCK_SIZE * 2., BLOCK_SIZE),
+        ),
+        (
+            Vec2::new(0., -(height + BLOCK_SIZE) / 2.),
+            Vec2::new(width + BLOCK_SIZE * 2., BLOCK_SIZE),
+        ),
+        (
+            Vec2::new(-(width + BLOCK_SIZE) / 2., 0.),
+            Vec2::new(BLOCK_SIZE, height),
+        ),
+        (
+            Vec2::new((width + BLOCK_SIZE) / 2., 0.),
+            Vec2::new(BLOCK_SIZE, height),
+        ),
+    ];
+    let border_color = if arena.wrap {
+        consts::WRAP_BORDER_COL
+    } else {
+        consts::BORDER_COL
+    };
+    for (center, size) in sides {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: border_color,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(center.extend(0.)),
+                ..default()
+            },
+            Wall,
+        ));
+    }
+    for obstacle in &arena.obstacles {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: consts::BORDER_COL,
+                    custom_size: Some(Vec2::new(BLOCK_SIZE, BLOCK_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(arena.translation(obstacle)),
+                ..default()
+            },
+            Wall,
+        ));
+    }
+}
+
+pub fn spawn_snake(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    players: Res<Players>,
+    difficulty: Res<Difficulty>,
+    speed_up: Res<SpeedUp>,
+) {
+    let tick = difficulty.tick_duration(0, speed_up.0);
+    for player in 0..players.0 {
+        let (head, direction) = arena.start(player, players.0);
+        commands.spawn((
+            Snake::new(&arena, head, direction),
+            Player(player),
+            MoveTimer(Timer::new(tick, TimerMode::Repeating)),
+            SpeedEffect::default(),
+        ));
+    }
+}
+
+fn food_bundle(food: Food, pos: VirtualPosition, arena: &Arena) -> impl Bundle {
+    (
         SpriteBundle {
             sprite: Sprite {
-                color: FOREGROUND_COL,
-                custom_size: Some(Vec2::new(BLOCK_SIZE, BLOCK_SIZE)),
+                color: food.color(),
+                custom_size: Some(Vec2::splat(food.size())),
                 ..default()
             },
+            transform: Transform::from_translation(arena.translation(&pos)),
             ..default()
         },
-        VirtualPosition { x: 5, y: 5 },
-        Food,
-    ));
+        pos,
+        food,
+    )
 }
 
-pub fn move_snake(
+pub fn spawn_food(mut commands: Commands, arena: Res<Arena>, snake_query: Query<&Snake>) {
+    let mut rng = rand::thread_rng();
+    let free_cell = arena.random_free_cell(&mut rng, |pos| {
+        snake_query.iter().any(|snake| snake.occupies(pos))
+    });
+    if let Some(pos) = free_cell {
+        commands.spawn(food_bundle(Food::Regular, pos, &arena));
+    }
+}
+
+/// Puts a random special food on a free cell from time to time.
+pub fn spawn_special_food(
     time: Res<Time>,
-    mut timer: ResMut<MainTimer>,
-    mut head: Query<(&mut VirtualPosition, &mut SnakeHead)>,
-    mut snake_parts_query: Query<
-        (Entity, &mut VirtualPosition, &mut Visibility),
-        (With<SnakePart>, Without<SnakeHead>),
-    >,
-    mut tail_query: Query<
-        &mut VirtualPosition,
-        (With<SnakeTail>, Without<SnakePart>, Without<SnakeHead>),
-    >,
+    arena: Res<Arena>,
+    mut timer: ResMut<SpecialFoodTimer>,
+    food_query: Query<(&VirtualPosition, &Food)>,
+    snake_query: Query<&Snake>,
+    mut commands: Commands,
 ) {
-    // If timer hasn't finished yet.
-    if !timer.tick(time.delta()).finished() {
+    if !timer.tick(time.delta()).just_finished() {
         return;
     }
-    let mut tail_pos = tail_query.single_mut();
-    let (mut head_position, mut head) = head.single_mut();
-    let mut last_pos = head_position.clone();
-    let new_position = match head.direction {
-        Direction::Up => VirtualPosition {
-            x: head_position.x,
-            y: head_position.y + 1,
-        },
-        Direction::Down => VirtualPosition {
-            x: head_position.x,
-            y: head_position.y - 1,
-        },
-        Direction::Left => VirtualPosition {
-            x: head_position.x - 1,
-            y: head_position.y,
-        },
-        Direction::Right => VirtualPosition {
-            x: head_position.x + 1,
-            y: head_position.y,
-        },
-    };
-    let snake_parts_sorted = snake_parts_query.iter_mut().sort::<Entity>();
-    for (_, mut position, mut visibility) in snake_parts_sorted {
-        let tmp = *position;
-        *position = last_pos;
-        last_pos = tmp;
-        *visibility = Visibility::Visible;
-    }
-    *tail_pos = last_pos;
-    *head_position = new_position;
-    if head.locked {
-        head.locked = false;
+    let special_count = food_query
+        .iter()
+        .filter(|(_, food)| **food != Food::Regular)
+        .count();
+    if special_count >= consts::MAX_SPECIAL_FOOD {
+        return;
     }
+    let mut rng = rand::thread_rng();
+    let Some(food) = Food::special().choose(&mut rng).copied() else {
+        return;
+    };
+    let free_cell = arena.random_free_cell(&mut rng, |pos| {
+        food_query.iter().any(|(food_pos, _)| food_pos == pos)
+            || snake_query.iter().any(|snake| snake.occupies(pos))
+    });
+    let (Some(pos), Some(lifetime)) = (free_cell, food.lifetime()) else {
+        return;
+    };
+    commands.spawn((
+        food_bundle(food, pos, &arena),
+        FoodTimer(Timer::new(lifetime, TimerMode::Once)),
+    ));
 }
 
-pub fn check_on_food(
-    head_query: Query<&VirtualPosition, With<SnakeHead>>,
-    food_query: Query<&VirtualPosition, With<Food>>,
-    mut eating_event_chan: EventWriter<EatingEvent>,
+/// Removes special food nobody has eaten in time,
+/// it blinks for a while before that.
+pub fn expire_food(
+    time: Res<Time>,
+    mut food_query: Query<(Entity, &mut FoodTimer, &mut Visibility)>,
+    mut commands: Commands,
 ) {
-    let head_pos = head_query.single();
-    let food_pos = food_query.single();
-    if head_pos == food_pos {
-        eating_event_chan.send(EatingEvent);
+    for (entity, mut timer, mut visibility) in &mut food_query {
+        if timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let left = timer.remaining_secs() as f64;
+        *visibility = if left < consts::FOOD_BLINK_TIME && (left * 8.) as i32 % 2 == 0 {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
     }
 }
 
-pub fn check_on_cannibalism(
-    head_query: Query<&VirtualPosition, With<SnakeHead>>,
-    snake_parts: Query<&VirtualPosition, (With<SnakePart>, Without<SnakeHead>)>,
+pub fn move_snake(
+    time: Res<Time>,
+    arena: Res<Arena>,
+    mut snake_query: Query<(&mut Snake, &mut MoveTimer, &Player)>,
+    food_query: Query<(Entity, &VirtualPosition, &Food)>,
+    mut eating_event_chan: EventWriter<EatingEvent>,
     mut gover_event: EventWriter<GameOverEvent>,
 ) {
-    let head_pos = head_query.single();
-    for part_pos in &mut snake_parts.iter() {
-        if head_pos == part_pos {
-            gover_event.send(GameOverEvent);
+    // Snakes whose timer has finished move in this frame.
+    let mut moving = Vec::new();
+    for (_, mut timer, player) in &mut snake_query {
+        if timer.tick(time.delta()).finished() {
+            moving.push(*player);
         }
     }
-}
-
-pub fn check_on_edge_running(
-    camera_query: Query<&Camera>,
-    head_query: Query<&Transform, With<SnakeHead>>,
-    mut gover_event: EventWriter<GameOverEvent>,
-) {
-    let camera = camera_query.single();
-    // A window size.
-    let Some(view_rect) = camera.physical_viewport_rect() else {
+    if moving.is_empty() {
         return;
-    };
-    let head_transform = head_query.single();
-    // Player reached the edge of the screen on x axis.
-    if view_rect.max.x as f32 / 2. <= head_transform.translation.x.abs() {
-        gover_event.send(GameOverEvent);
     }
-    // Player reached the edge of the screen on y axis.
-    if view_rect.max.y as f32 / 2. <= head_transform.translation.y.abs() {
-        gover_event.send(GameOverEvent);
+    // Collisions between the snakes are found before any of them moves.
+    let crashed = snake_query
+        .iter()
+        .filter(|(_, _, player)| moving.contains(player))
+        .filter(|(snake, _, player)| {
+            snake_query.iter().any(|(other, _, other_player)| {
+                other_player != *player
+                    && snake.crashes_into(other, moving.contains(other_player), &arena)
+            })
+        })
+        .map(|(_, _, player)| *player)
+        .collect::<Vec<_>>();
+    for (mut snake, _, player) in &mut snake_query {
+        if !moving.contains(player) {
+            continue;
+        }
+        if crashed.contains(player) || snake.step(&arena) == Step::Crashed {
+            gover_event.send(GameOverEvent { player: player.0 });
+            continue;
+        }
+        for (entity, food_pos, food) in &food_query {
+            if *food_pos == snake.head() {
+                eating_event_chan.send(EatingEvent {
+                    player: player.0,
+                    entity,
+                    food: *food,
+                });
+            }
+        }
     }
 }
 
 pub fn eat_food(
-    mut food_pos_query: Query<(&mut VirtualPosition, &mut Visibility), With<Food>>,
-    snake_parts: Query<&VirtualPosition, (With<SnakePart>, Without<Food>)>,
-    camera_query: Query<&Camera>,
+    mut eating_events: EventReader<EatingEvent>,
+    mut food_query: Query<(&mut VirtualPosition, &mut Visibility), With<Food>>,
+    snake_query: Query<&Snake>,
+    arena: Res<Arena>,
     mut score: ResMut<Score>,
+    mut speed_query: Query<(&mut SpeedEffect, &Player)>,
+    mut commands: Commands,
 ) {
-    let camera = camera_query.single();
-    let (mut food_position, mut visibility) = food_pos_query.single_mut();
-    *visibility = Visibility::Hidden;
-    let mut pos = HashSet::<(i32, i32)>::new();
-    for snake_part_pos in &mut snake_parts.iter() {
-        pos.insert((snake_part_pos.x, snake_part_pos.y));
-    }
-    let Some(view_rect) = camera.physical_viewport_rect() else {
-        return;
-    };
-    // This expression calculates the number of blocks that can fit in the window.
-    let x_blocks = view_rect.max.x as f32 / BLOCK_SIZE - BLOCK_SIZE;
-    let y_blocks = view_rect.max.y as f32 / BLOCK_SIZE - BLOCK_SIZE;
-    // We divide by 2 to get the number of blocks that can fit in half of the window.
-    let virt_x_max = (x_blocks / 2.) as i32;
-    let virt_y_max = (y_blocks / 2.) as i32;
-    let mut rng = rand::thread_rng();
-    loop {
-        let x: i32 = rng.gen_range(-virt_x_max..virt_x_max);
-        let y: i32 = rng.gen_range(-virt_y_max..virt_y_max);
-        if !pos.contains(&(x, y)) {
-            *food_position = VirtualPosition { x, y };
-            break;
+    for event in eating_events.read() {
+        score.0[event.player] += event.food.points();
+        // Speed items change the speed of the snake which has eaten them.
+        let factor = match event.food {
+            Food::SpeedUp => Some(consts::SPEED_UP_EFFECT),
+            Food::SlowDown => Some(consts::SLOW_DOWN_EFFECT),
+            Food::Regular | Food::Bonus | Food::Shrink => None,
+        };
+        if let Some(factor) = factor {
+            for (mut speed_effect, player) in &mut speed_query {
+                if player.0 == event.player {
+                    speed_effect.start(factor);
+                }
+            }
+        }
+        // Special food is eaten only once,
+        // the regular one moves to a new place.
+        if event.food != Food::Regular {
+            if let Some(entity) = commands.get_entity(event.entity) {
+                entity.despawn_recursive();
+            }
+            continue;
+        }
+        let food_positions = food_query.iter().map(|(pos, _)| *pos).collect::<Vec<_>>();
+        let mut rng = rand::thread_rng();
+        let free_cell = arena.random_free_cell(&mut rng, |pos| {
+            food_positions.contains(pos) || snake_query.iter().any(|snake| snake.occupies(pos))
+        });
+        let Ok((mut food_position, mut visibility)) = food_query.get_mut(event.entity) else {
+            continue;
+        };
+        // There is no place for the food, when the snake takes the whole arena.
+        match free_cell {
+            Some(pos) => *food_position = pos,
+            None => *visibility = Visibility::Hidden,
         }
     }
-    *visibility = Visibility::Visible;
-    score.0 += 1;
 }
 
-pub fn grow_snake(camera_query: Query<&Camera>, mut commands: Commands) {
-    let camera = camera_query.single();
-    let Some(view_rect) = camera.physical_viewport_rect() else {
-        return;
-    };
-    commands.spawn((
-        SpriteBundle {
-            sprite: Sprite {
-                color: FOREGROUND_COL,
-                custom_size: Some(Vec2::new(BLOCK_SIZE, BLOCK_SIZE)),
-                ..default()
-            },
-            visibility: Visibility::Hidden,
-            transform: Transform::from_translation(Vec3::new(
-                view_rect.max.x as f32 * 2.,
-                0.0,
-                0.0,
-            )),
-            ..default()
-        },
-        VirtualPosition {
-            x: view_rect.max.x as i32,
-            y: view_rect.max.y as i32,
-        },
-        SnakePart,
-    ));
+pub fn grow_snake(
+    mut eating_events: EventReader<EatingEvent>,
+    mut snake_query: Query<(&mut Snake, &Player)>,
+) {
+    for event in eating_events.read() {
+        for (mut snake, player) in &mut snake_query {
+            if player.0 != event.player {
+                continue;
+            }
+            match event.food {
+                Food::Regular | Food::Bonus => snake.grow(1),
+                Food::Shrink => snake.shrink(consts::SHRINK_SEGMENTS),
+                Food::SpeedUp | Food::SlowDown => {}
+            }
+        }
+    }
 }
 
-pub fn game_over(mut app_state: ResMut<NextState<GameState>>) {
+/// Ends the game, when a snake crashes.
+/// The other snake wins, unless it has crashed at the same time.
+pub fn game_over(
+    mut gover_events: EventReader<GameOverEvent>,
+    players: Res<Players>,
+    mut winner: ResMut<Winner>,
+    mut app_state: ResMut<NextState<GameState>>,
+) {
+    let crashed = gover_events
+        .read()
+        .map(|event| event.player)
+        .collect::<Vec<_>>();
+    let survivors = (0..players.0)
+        .filter(|player| !crashed.contains(player))
+        .collect::<Vec<_>>();
+    winner.0 = match survivors.as_slice() {
+        [survivor] if players.0 > 1 => Some(*survivor),
+        _ => None,
+    };
     app_state.set(GameState::GameOver);
 }
 
-pub fn handle_input(keys: Res<ButtonInput<KeyCode>>, mut head_query: Query<&mut SnakeHead>) {
-    let mut head = head_query.single_mut();
-    let mut new_direction = head.direction;
-    if keys.just_pressed(KeyCode::ArrowUp) && head.direction != Direction::Down {
-        new_direction = Direction::Up;
-    }
-    if keys.just_pressed(KeyCode::ArrowDown) && head.direction != Direction::Up {
-        new_direction = Direction::Down;
-    }
-    if keys.just_pressed(KeyCode::ArrowRight) && head.direction != Direction::Left {
-        new_direction = Direction::Right;
-    }
-    if keys.just_pressed(KeyCode::ArrowLeft) && head.direction != Direction::Right {
-        new_direction = Direction::Left;
-    }
-    if !head.locked && head.direction != new_direction {
-        head.direction = new_direction;
-        head.locked = true;
+/// The first player uses the arrows, the second one uses WASD.
+pub fn handle_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut snake_query: Query<(&mut Snake, &Player)>,
+) {
+    let controls = [
+        [
+            (KeyCode::ArrowUp, Direction::Up),
+            (KeyCode::ArrowDown, Direction::Down),
+            (KeyCode::ArrowRight, Direction::Right),
+            (KeyCode::ArrowLeft, Direction::Left),
+        ],
+        [
+            (KeyCode::KeyW, Direction::Up),
+            (KeyCode::KeyS, Direction::Down),
+            (KeyCode::KeyD, Direction::Right),
+            (KeyCode::KeyA, Direction::Left),
+        ],
+    ];
+    for (mut snake, player) in &mut snake_query {
+        for (key, turn) in controls[player.0] {
+            if keys.just_pressed(key) {
+                snake.queue_turn(turn);
+            }
+        }
     }
 }
 
 pub fn despawn_snake(
-    head_query: Query<Entity, With<SnakeHead>>,
-    snake_query: Query<Entity, With<SnakePart>>,
-    tail_query: Query<Entity, With<SnakeTail>>,
+    snake_query: Query<Entity, With<Snake>>,
+    parts_query: Query<Entity, With<SnakePart>>,
     food_query: Query<Entity, With<Food>>,
+    wall_query: Query<Entity, With<Wall>>,
     mut commands: Commands,
 ) {
-    for entity in &head_query {
-        commands.entity(entity).despawn_recursive();
-    }
     for entity in &snake_query {
         commands.entity(entity).despawn_recursive();
     }
-    for entity in &tail_query {
+    for entity in &parts_query {
         commands.entity(entity).despawn_recursive();
     }
     for entity in &food_query {
         commands.entity(entity).despawn_recursive();
     }
+    for entity in &wall_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Keeps a sprite on every segment of every snake,
+/// spawning and despawning them as the snakes change their length.
+pub fn render_snake(
+    arena: Res<Arena>,
+    snake_query: Query<(&Snake, &Player)>,
+    mut parts_query: Query<(Entity, &SnakePart, &Player, &mut Transform)>,
+    mut commands: Commands,
+) {
+    let mut rendered = [0; consts::MAX_PLAYERS];
+    for (entity, part, player, mut transform) in &mut parts_query {
+        let segment = snake_query
+            .iter()
+            .find(|(_, snake_player)| *snake_player == player)
+            .and_then(|(snake, _)| snake.segment(part.0));
+        match segment {
+            Some(pos) => {
+                transform.translation = arena.translation(&pos);
+                rendered[player.0] += 1;
+            }
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+    for (snake, player) in &snake_query {
+        for (index, pos) in snake.body().enumerate().skip(rendered[player.0]) {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: consts::PLAYER_COLS[player.0],
+                        custom_size: Some(Vec2::new(BLOCK_SIZE, BLOCK_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(arena.translation(pos)),
+                    ..default()
+                },
+                SnakePart(index),
+                *player,
+            ));
+        }
+    }
 }
 
-pub fn position_translation(mut q: Query<(&VirtualPosition, &mut Transform)>) {
+pub fn position_translation(arena: Res<Arena>, mut q: Query<(&VirtualPosition, &mut Transform)>) {
     for (pos, mut transform) in q.iter_mut() {
-        transform.translation = Vec3::new(
-            pos.x as f32 * consts::BLOCK_SIZE,
-            pos.y as f32 * consts::BLOCK_SIZE,
-            0.0,
-        );
+        transform.translation = arena.translation(pos);
     }
 }
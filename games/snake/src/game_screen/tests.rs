@@ -0,0 +1,308 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::{
+    components::{Direction, VirtualPosition},
+    resources::Arena,
+    snake::{Snake, Step},
+};
+
+fn pos(x: i32, y: i32) -> VirtualPosition {
+    VirtualPosition { x, y }
+}
+
+fn arena(width: i32, height: i32) -> Arena {
+    Arena {
+        width,
+        height,
+        ..Arena::default()
+    }
+}
+
+/// Snake of the given length, stretched to the left of the head.
+fn snake_with_length(arena: &Arena, head: VirtualPosition, length: usize) -> Snake {
+    let mut snake = Snake::new(
+        arena,
+        pos(head.x - length as i32 + 1, head.y),
+        Direction::Right,
+    );
+    snake.grow(length - 1);
+    for _ in 1..length {
+        assert_eq!(snake.step(arena), Step::Moved);
+    }
+    snake
+}
+
+#[test]
+fn snake_moves_in_its_direction() {
+    let arena = arena(10, 10);
+    let mut snake = Snake::new(&arena, pos(2, 2), Direction::Right);
+    assert_eq!(snake.step(&arena), Step::Moved);
+    assert_eq!(snake.head(), pos(3, 2));
+    assert_eq!(snake.body().count(), 1);
+    assert!(snake.occupies(&pos(3, 2)));
+    assert!(!snake.occupies(&pos(2, 2)));
+}
+
+#[test]
+fn body_follows_the_head() {
+    let arena = arena(10, 10);
+    let mut snake = snake_with_length(&arena, pos(4, 2), 3);
+    snake.queue_turn(Direction::Up);
+    assert_eq!(snake.step(&arena), Step::Moved);
+    assert_eq!(
+        snake.body().copied().collect::<Vec<_>>(),
+        [pos(4, 3), pos(4, 2), pos(3, 2)]
+    );
+    assert!(!snake.occupies(&pos(2, 2)));
+}
+
+#[test]
+fn queued_turns_are_applied_one_per_move() {
+    let arena = arena(10, 10);
+    let mut snake = snake_with_length(&arena, pos(4, 4), 3);
+    // Up and then left within a single tick.
+    assert!(snake.queue_turn(Direction::Up));
+    assert!(snake.queue_turn(Direction::Left));
+    snake.step(&arena);
+    assert_eq!(snake.head(), pos(4, 5));
+    snake.step(&arena);
+    assert_eq!(snake.head(), pos(3, 5));
+    snake.step(&arena);
+    assert_eq!(snake.head(), pos(2, 5));
+}
+
+#[test]
+fn reversing_into_the_neck_is_ignored() {
+    let arena = arena(10, 10);
+    let mut snake = snake_with_length(&arena, pos(4, 4), 3);
+    assert!(!snake.queue_turn(Direction::Left));
+    assert!(!snake.queue_turn(Direction::Right));
+    // The reverse is checked against the queued turn.
+    assert!(snake.queue_turn(Direction::Up));
+    assert!(!snake.queue_turn(Direction::Down));
+    assert_eq!(snake.step(&arena), Step::Moved);
+}
+
+#[test]
+fn turn_queue_is_bounded() {
+    let arena = arena(10, 10);
+    let mut snake = Snake::new(&arena, pos(4, 4), Direction::Right);
+    let turns = [
+        Direction::Up,
+        Direction::Left,
+        Direction::Down,
+        Direction::Right,
+    ];
+    let queued = turns
+        .into_iter()
+        .filter(|turn| snake.queue_turn(*turn))
+        .count();
+    assert_eq!(queued, crate::consts::INPUT_QUEUE_SIZE);
+}
+
+#[test]
+fn snake_grows_at_the_end_of_the_tail() {
+    let arena = arena(10, 10);
+    let mut snake = Snake::new(&arena, pos(2, 2), Direction::Right);
+    snake.grow(2);
+    snake.step(&arena);
+    assert_eq!(snake.body().count(), 2);
+    snake.step(&arena);
+    assert_eq!(snake.body().count(), 3);
+    snake.step(&arena);
+    assert_eq!(snake.body().count(), 3);
+    assert_eq!(
+        snake.body().copied().collect::<Vec<_>>(),
+        [pos(5, 2), pos(4, 2), pos(3, 2)]
+    );
+}
+
+#[test]
+fn shrinking_keeps_the_head() {
+    let arena = arena(10, 10);
+    let mut snake = snake_with_length(&arena, pos(6, 2), 5);
+    snake.shrink(3);
+    assert_eq!(
+        snake.body().copied().collect::<Vec<_>>(),
+        [pos(6, 2), pos(5, 2)]
+    );
+    assert!(!snake.occupies(&pos(4, 2)));
+    snake.shrink(3);
+    assert_eq!(snake.body().copied().collect::<Vec<_>>(), [pos(6, 2)]);
+}
+
+#[test]
+fn shrinking_cancels_pending_growth_first() {
+    let arena = arena(10, 10);
+    let mut snake = snake_with_length(&arena, pos(4, 2), 3);
+    snake.grow(2);
+    snake.shrink(3);
+    assert_eq!(snake.body().count(), 2);
+    snake.step(&arena);
+    assert_eq!(snake.body().count(), 2);
+}
+
+#[test]
+fn snake_crashes_into_itself() {
+    let arena = arena(10, 10);
+    let mut snake = snake_with_length(&arena, pos(5, 5), 5);
+    for turn in [Direction::Up, Direction::Left, Direction::Down] {
+        snake.queue_turn(turn);
+    }
+    assert_eq!(snake.step(&arena), Step::Moved);
+    assert_eq!(snake.step(&arena), Step::Moved);
+    assert_eq!(snake.step(&arena), Step::Crashed);
+    assert_eq!(snake.head(), pos(4, 6));
+}
+
+#[test]
+fn snake_can_follow_its_tail() {
+    let arena = arena(10, 10);
+    let mut snake = snake_with_length(&arena, pos(5, 5), 4);
+    for turn in [Direction::Up, Direction::Left, Direction::Down] {
+        snake.queue_turn(turn);
+    }
+    // The head enters the cell the end of the tail leaves.
+    for _ in 0..3 {
+        assert_eq!(snake.step(&arena), Step::Moved);
+    }
+    assert_eq!(snake.head(), pos(4, 5));
+    assert!(snake.occupies(&pos(4, 5)));
+}
+
+#[test]
+fn snake_crashes_into_walls_and_obstacles() {
+    let arena = arena(3, 3);
+    let mut snake = Snake::new(&arena, pos(2, 1), Direction::Right);
+    assert_eq!(snake.step(&arena), Step::Crashed);
+    assert_eq!(snake.head(), pos(2, 1));
+
+    let arena = "...\n.#.\n...".parse::<Arena>().unwrap();
+    let mut snake = Snake::new(&arena, pos(0, 1), Direction::Right);
+    assert_eq!(snake.step(&arena), Step::Crashed);
+}
+
+#[test]
+fn snake_crashes_into_the_body_of_another_one() {
+    let arena = arena(10, 10);
+    let mut snake = Snake::new(&arena, pos(4, 4), Direction::Right);
+    snake.queue_turn(Direction::Up);
+    let other = snake_with_length(&arena, pos(6, 5), 4);
+    assert!(snake.crashes_into(&other, true, &arena));
+    assert!(!other.crashes_into(&snake, true, &arena));
+}
+
+#[test]
+fn snakes_crash_head_to_head() {
+    let arena = arena(10, 10);
+    // Both heads move into the same cell.
+    let left = Snake::new(&arena, pos(3, 4), Direction::Right);
+    let right = Snake::new(&arena, pos(5, 4), Direction::Left);
+    assert!(left.crashes_into(&right, true, &arena));
+    assert!(right.crashes_into(&left, true, &arena));
+    // Heads swap their cells.
+    let left = Snake::new(&arena, pos(4, 4), Direction::Right);
+    assert!(left.crashes_into(&right, true, &arena));
+    assert!(right.crashes_into(&left, true, &arena));
+}
+
+#[test]
+fn parallel_snakes_do_not_crash() {
+    let arena = arena(10, 10);
+    let first = Snake::new(&arena, pos(4, 4), Direction::Right);
+    let second = Snake::new(&arena, pos(4, 5), Direction::Left);
+    assert!(!first.crashes_into(&second, true, &arena));
+    assert!(!second.crashes_into(&first, true, &arena));
+}
+
+#[test]
+fn snake_passes_in_front_of_a_waiting_one() {
+    let arena = arena(10, 10);
+    let left = Snake::new(&arena, pos(3, 4), Direction::Right);
+    let right = Snake::new(&arena, pos(5, 4), Direction::Left);
+    assert!(!left.crashes_into(&right, false, &arena));
+    assert!(!right.crashes_into(&left, false, &arena));
+}
+
+#[test]
+fn snake_wraps_around() {
+    let arena = Arena {
+        wrap: true,
+        ..arena(3, 3)
+    };
+    let mut snake = Snake::new(&arena, pos(2, 1), Direction::Right);
+    assert_eq!(snake.step(&arena), Step::Moved);
+    assert_eq!(snake.head(), pos(0, 1));
+    snake.queue_turn(Direction::Down);
+    snake.step(&arena);
+    snake.step(&arena);
+    assert_eq!(snake.head(), pos(0, 2));
+}
+
+#[test]
+fn maps_are_parsed_top_to_bottom() {
+    let arena = "#..\n...\n..#".parse::<Arena>().unwrap();
+    assert_eq!((arena.width, arena.height), (3, 3));
+    assert!(arena.obstacles.contains(&pos(0, 2)));
+    assert!(arena.obstacles.contains(&pos(2, 0)));
+    assert_eq!(arena.obstacles.len(), 2);
+    assert!("#..\n..".parse::<Arena>().is_err());
+    assert!("#x.".parse::<Arena>().is_err());
+}
+
+#[test]
+fn food_is_placed_on_free_cells() {
+    let arena = "....\n.#..\n....".parse::<Arena>().unwrap();
+    let snake = snake_with_length(&arena, pos(3, 0), 4);
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..100 {
+        let food = arena
+            .random_free_cell(&mut rng, |pos| snake.occupies(pos))
+            .unwrap();
+        assert!(arena.is_free(&food));
+        assert!(!snake.occupies(&food));
+    }
+}
+
+#[test]
+fn no_food_when_the_arena_is_full() {
+    let arena = arena(3, 1);
+    let snake = snake_with_length(&arena, pos(2, 0), 3);
+    let mut rng = StdRng::seed_from_u64(7);
+    assert_eq!(
+        arena.random_free_cell(&mut rng, |pos| snake.occupies(pos)),
+        None
+    );
+}
+
+#[test]
+fn bundled_maps_are_valid() {
+    for (name, map) in crate::consts::MAPS {
+        let arena = map.parse::<Arena>().unwrap();
+        for players in 1..=crate::consts::MAX_PLAYERS {
+            for player in 0..players {
+                // There is some room in front of every snake at the start.
+                let (head, direction) = arena.start(player, players);
+                let mut snake = Snake::new(&arena, head, direction);
+                for _ in 0..3 {
+                    assert_eq!(snake.step(&arena), Step::Moved, "{name}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn snakes_start_apart() {
+    let arena = arena(38, 38);
+    let (head, direction) = arena.start(0, 2);
+    let mut first = Snake::new(&arena, head, direction);
+    let (head, direction) = arena.start(1, 2);
+    let mut second = Snake::new(&arena, head, direction);
+    for _ in 0..10 {
+        assert!(!first.crashes_into(&second, true, &arena));
+        assert!(!second.crashes_into(&first, true, &arena));
+        first.step(&arena);
+        second.step(&arena);
+    }
+}
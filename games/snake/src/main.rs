@@ -29,7 +29,12 @@ fn main() {
             ..default()
         }))
         .init_state::<state::GameState>()
-        .insert_resource(state::Score(0))
+        .init_resource::<state::Score>()
+        .init_resource::<state::Players>()
+        .init_resource::<state::Winner>()
+        .init_resource::<state::ArenaMode>()
+        .init_resource::<state::Difficulty>()
+        .init_resource::<state::SpeedUp>()
         .add_systems(Startup, setup_camera)
         .add_systems(Update, exit_game)
         .add_plugins(cgc_lib::CGCCommonAssetsPlugin)
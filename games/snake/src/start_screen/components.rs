@@ -8,4 +8,24 @@ pub struct OnMenuScreen;
 pub enum ButtonAction {
     Quit,
     StartGame,
+    ChangePlayers,
+    ChangeMode,
+    ChangeDifficulty,
+    ToggleSpeedUp,
 }
+
+/// Text of the button, which shows the selected arena.
+#[derive(Component, Debug)]
+pub struct ModeLabel;
+
+/// Text of the button, which shows the selected difficulty.
+#[derive(Component, Debug)]
+pub struct DifficultyLabel;
+
+/// Text of the button, which shows if the speed up is on.
+#[derive(Component, Debug)]
+pub struct SpeedUpLabel;
+
+/// Text of the button, which shows the number of players.
+#[derive(Component, Debug)]
+pub struct PlayersLabel;
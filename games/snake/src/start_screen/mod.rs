@@ -1,5 +1,8 @@
 use bevy::prelude::*;
-use systems::{focused_button_decoration, on_select_item};
+use systems::{
+    change_difficulty, change_mode, change_players, focused_button_decoration, on_select_item,
+    toggle_speed_up,
+};
 
 use crate::{
     state::GameState,
@@ -18,6 +21,10 @@ impl Plugin for StartScreenPlugin {
                 (
                     vert_menu_controls,
                     on_select_item,
+                    change_players,
+                    change_mode,
+                    change_difficulty,
+                    toggle_speed_up,
                     focused_button_decoration,
                 )
                     .run_if(in_state(GameState::StartMenu)),
@@ -1,10 +1,23 @@
 use bevy::prelude::*;
 
-use crate::{consts, state::GameState, utils::components::HasFocus};
+use crate::{
+    consts,
+    state::{ArenaMode, Difficulty, GameState, Players, SpeedUp},
+    utils::components::HasFocus,
+};
 
-use super::components::{ButtonAction, OnMenuScreen};
+use super::components::{
+    ButtonAction, DifficultyLabel, ModeLabel, OnMenuScreen, PlayersLabel, SpeedUpLabel,
+};
 
-pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn render_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    arena_mode: Res<ArenaMode>,
+    difficulty: Res<Difficulty>,
+    speed_up: Res<SpeedUp>,
+    players: Res<Players>,
+) {
     let text_style = TextStyle {
         font_size: 16.,
         font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
@@ -69,6 +82,102 @@ pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
                                 ..default()
                             });
                         });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    border: UiRect::all(Val::Px(3.)),
+                                    padding: UiRect::all(Val::Percent(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ButtonAction::ChangePlayers,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        players_label(*players),
+                                        text_style.clone(),
+                                    ),
+                                    ..default()
+                                },
+                                PlayersLabel,
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    border: UiRect::all(Val::Px(3.)),
+                                    padding: UiRect::all(Val::Percent(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ButtonAction::ChangeMode,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        mode_label(*arena_mode),
+                                        text_style.clone(),
+                                    ),
+                                    ..default()
+                                },
+                                ModeLabel,
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    border: UiRect::all(Val::Px(3.)),
+                                    padding: UiRect::all(Val::Percent(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ButtonAction::ChangeDifficulty,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        difficulty_label(*difficulty),
+                                        text_style.clone(),
+                                    ),
+                                    ..default()
+                                },
+                                DifficultyLabel,
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    border: UiRect::all(Val::Px(3.)),
+                                    padding: UiRect::all(Val::Percent(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ButtonAction::ToggleSpeedUp,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        speed_up_label(*speed_up),
+                                        text_style.clone(),
+                                    ),
+                                    ..default()
+                                },
+                                SpeedUpLabel,
+                            ));
+                        });
                     #[cfg(not(target_arch = "wasm32"))]
                     parent
                         .spawn((
@@ -104,6 +213,22 @@ pub fn focused_button_decoration(
     }
 }
 
+fn players_label(players: Players) -> String {
+    format!("PLAYERS: {}", players.0)
+}
+
+fn mode_label(mode: ArenaMode) -> String {
+    format!("MODE: {mode}")
+}
+
+fn difficulty_label(difficulty: Difficulty) -> String {
+    format!("DIFFICULTY: {difficulty}")
+}
+
+fn speed_up_label(speed_up: SpeedUp) -> String {
+    format!("SPEED UP: {}", if speed_up.0 { "ON" } else { "OFF" })
+}
+
 pub fn on_select_item(
     inputs: Res<ButtonInput<KeyCode>>,
     focued_button_query: Query<&ButtonAction, With<HasFocus>>,
@@ -118,6 +243,88 @@ pub fn on_select_item(
             ButtonAction::StartGame => {
                 app_state.set(GameState::Playing);
             }
+            // Settings are changed by their own systems.
+            _ => {}
         }
     }
 }
+
+pub fn change_players(
+    inputs: Res<ButtonInput<KeyCode>>,
+    focued_button_query: Query<&ButtonAction, With<HasFocus>>,
+    mut players: ResMut<Players>,
+    mut label_query: Query<&mut Text, With<PlayersLabel>>,
+) {
+    if !inputs.just_pressed(KeyCode::Enter)
+        || !matches!(
+            focued_button_query.get_single(),
+            Ok(ButtonAction::ChangePlayers)
+        )
+    {
+        return;
+    }
+    players.0 = players.0 % consts::MAX_PLAYERS + 1;
+    for mut label in &mut label_query {
+        label.sections[0].value = players_label(*players);
+    }
+}
+
+pub fn change_mode(
+    inputs: Res<ButtonInput<KeyCode>>,
+    focued_button_query: Query<&ButtonAction, With<HasFocus>>,
+    mut arena_mode: ResMut<ArenaMode>,
+    mut label_query: Query<&mut Text, With<ModeLabel>>,
+) {
+    if !inputs.just_pressed(KeyCode::Enter)
+        || !matches!(
+            focued_button_query.get_single(),
+            Ok(ButtonAction::ChangeMode)
+        )
+    {
+        return;
+    }
+    *arena_mode = arena_mode.next();
+    for mut label in &mut label_query {
+        label.sections[0].value = mode_label(*arena_mode);
+    }
+}
+
+pub fn change_difficulty(
+    inputs: Res<ButtonInput<KeyCode>>,
+    focued_button_query: Query<&ButtonAction, With<HasFocus>>,
+    mut difficulty: ResMut<Difficulty>,
+    mut label_query: Query<&mut Text, With<DifficultyLabel>>,
+) {
+    if !inputs.just_pressed(KeyCode::Enter)
+        || !matches!(
+            focued_button_query.get_single(),
+            Ok(ButtonAction::ChangeDifficulty)
+        )
+    {
+        return;
+    }
+    *difficulty = difficulty.next();
+    for mut label in &mut label_query {
+        label.sections[0].value = difficulty_label(*difficulty);
+    }
+}
+
+pub fn toggle_speed_up(
+    inputs: Res<ButtonInput<KeyCode>>,
+    focued_button_query: Query<&ButtonAction, With<HasFocus>>,
+    mut speed_up: ResMut<SpeedUp>,
+    mut label_query: Query<&mut Text, With<SpeedUpLabel>>,
+) {
+    if !inputs.just_pressed(KeyCode::Enter)
+        || !matches!(
+            focued_button_query.get_single(),
+            Ok(ButtonAction::ToggleSpeedUp)
+        )
+    {
+        return;
+    }
+    speed_up.0 = !speed_up.0;
+    for mut label in &mut label_query {
+        label.sections[0].value = speed_up_label(*speed_up);
+    }
+}
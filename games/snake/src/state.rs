@@ -1,7 +1,27 @@
+use std::{fmt, time::Duration};
+
 use bevy::prelude::*;
 
-#[derive(Resource)]
-pub struct Score(pub usize);
+use crate::consts;
+
+/// Points of every player.
+#[derive(Resource, Default)]
+pub struct Score(pub [usize; consts::MAX_PLAYERS]);
+
+/// Number of snakes in the arena.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Players(pub usize);
+
+impl Default for Players {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Player who has survived the other one,
+/// there is none in the single player game and in a draw.
+#[derive(Resource, Debug, Default)]
+pub struct Winner(pub Option<usize>);
 
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum GameState {
@@ -10,3 +30,92 @@ pub enum GameState {
     Playing,
     GameOver,
 }
+
+/// Arena selected in the start menu.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArenaMode {
+    // Running into the border ends the game.
+    #[default]
+    Walls,
+    // The snake re-enters the arena from the opposite side.
+    Wrap,
+    // Walls with obstacles from one of the maps.
+    Map(usize),
+}
+
+impl ArenaMode {
+    pub fn next(&self) -> Self {
+        match self {
+            ArenaMode::Walls => ArenaMode::Wrap,
+            ArenaMode::Wrap => ArenaMode::Map(0),
+            ArenaMode::Map(index) if index + 1 < consts::MAPS.len() => ArenaMode::Map(index + 1),
+            ArenaMode::Map(_) => ArenaMode::Walls,
+        }
+    }
+}
+
+impl fmt::Display for ArenaMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArenaMode::Walls => f.write_str("WALLS"),
+            ArenaMode::Wrap => f.write_str("WRAP"),
+            ArenaMode::Map(index) => f.write_str(consts::MAPS[*index].0),
+        }
+    }
+}
+
+/// Speed of the snake, selected in the start menu.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+    Insane,
+}
+
+impl Difficulty {
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Slow => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Fast,
+            Difficulty::Fast => Difficulty::Insane,
+            Difficulty::Insane => Difficulty::Slow,
+        }
+    }
+
+    /// Time between moves of the snake.
+    ///
+    /// With the speed up the tick gets shorter as the score grows,
+    /// until it reaches the minimum.
+    pub fn tick_duration(&self, score: usize, speed_up: bool) -> Duration {
+        let base = match self {
+            Difficulty::Slow => consts::TICK_DURATION * 2.,
+            Difficulty::Normal => consts::TICK_DURATION,
+            Difficulty::Fast => consts::TICK_DURATION * 0.7,
+            Difficulty::Insane => consts::TICK_DURATION * 0.45,
+        };
+        if !speed_up {
+            return Duration::from_secs_f64(base);
+        }
+        let steps = (score / consts::SPEED_UP_STEP) as i32;
+        let tick = base * consts::SPEED_UP_FACTOR.powi(steps);
+        Duration::from_secs_f64(tick.max(consts::MIN_TICK_DURATION.min(base)))
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Slow => "SLOW",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Fast => "FAST",
+            Difficulty::Insane => "INSANE",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The snake speeds up as the score grows.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpeedUp(pub bool);
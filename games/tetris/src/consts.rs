@@ -1,8 +1,66 @@
+use std::time::Duration;
+
 use bevy::color::Color;
 
 pub const SOFT_DROP_MULTIPLER: u32 = 3;
-pub const START_X_POSITION: i32 = 4;
-pub const START_Y_POSITION: i32 = 18;
+// Delay before a held horizontal move starts repeating (DAS).
+pub const AUTO_SHIFT_DELAY: Duration = Duration::from_millis(170);
+// Interval between repeated moves (ARR), zero moves instantly to the wall.
+pub const AUTO_REPEAT_RATE: Duration = Duration::from_millis(50);
+// Choices of the auto-shift delay and the repeat rate in the start menu.
+pub const AUTO_SHIFT_DELAYS: [Duration; 4] = [
+    Duration::from_millis(100),
+    Duration::from_millis(133),
+    AUTO_SHIFT_DELAY,
+    Duration::from_millis(250),
+];
+pub const AUTO_REPEAT_RATES: [Duration; 5] = [
+    Duration::ZERO,
+    Duration::from_millis(16),
+    Duration::from_millis(33),
+    AUTO_REPEAT_RATE,
+    Duration::from_millis(83),
+];
+pub const LINES_PER_LEVEL: usize = 10;
+// Duration of the line clear animation, before the rows collapse.
+pub const LINE_CLEAR_DURATION: Duration = Duration::from_millis(300);
+// Time a grounded figure waits before it's locked.
+pub const LOCK_DELAY: Duration = Duration::from_millis(500);
+// How many times a grounded figure can postpone its lockdown
+// by moving or rotating, the last reset still waits the full delay.
+pub const MAX_LOCK_RESETS: usize = 15;
+// Gravity stops speeding up after this level.
+pub const MAX_SPEED_LEVEL: usize = 20;
+// Points for clearing 1, 2, 3 or 4 lines at once.
+// They are multiplied by the current level.
+pub const LINE_CLEAR_POINTS: [usize; 4] = [100, 300, 500, 800];
+// Points for t-spin minis and t-spins clearing 0, 1, 2 (or 3) lines.
+pub const T_SPIN_MINI_POINTS: [usize; 3] = [100, 200, 400];
+pub const T_SPIN_POINTS: [usize; 4] = [400, 800, 1200, 1600];
+// Points for every consecutive clear in a combo.
+pub const COMBO_POINTS: usize = 50;
+// Duration of the top out animation, before the game over screen.
+pub const TOP_OUT_DURATION: Duration = Duration::from_millis(1000);
+// Lines to clear in the sprint mode.
+pub const SPRINT_LINES: usize = 40;
+// Duration of the ultra mode.
+pub const ULTRA_TIME_LIMIT: Duration = Duration::from_secs(120);
+// Garbage rows the dig modes start with.
+pub const DIG_GARBAGE_ROWS: usize = 10;
+// Interval between new garbage rows in the rising tide mode.
+pub const RISING_TIDE_INTERVAL: Duration = Duration::from_secs(8);
+// Players of the local versus game.
+pub const MAX_PLAYERS: usize = 2;
+// Garbage rows sent to the opponent for clearing 1, 2, 3 or 4 lines.
+pub const GARBAGE_LINES: [usize; 4] = [0, 1, 2, 4];
+// Interval between inputs of the bot.
+pub const BOT_INPUT_INTERVAL: Duration = Duration::from_millis(100);
+// Idle time in the start menu before the bot starts a demo game.
+pub const ATTRACT_DELAY: Duration = Duration::from_secs(15);
+// File the last played game is saved to.
+pub const REPLAY_FILE: &str = "tetris.replay";
+// How many upcoming figures are shown in the side panel.
+pub const NEXT_FIGURES_COUNT: usize = 3;
 pub const BACKGROUND_COLOR: Color = Color::srgb(0., 0., 0.);
 pub const FOREGROUND_COLOR: Color = Color::srgb(0., 1., 0.);
 pub const LIGHT_FOREGROUND_COLOR: Color = Color::srgb(0., 0.8, 0.);
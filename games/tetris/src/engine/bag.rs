@@ -0,0 +1,50 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::figures::FigureType;
+
+/// Random generator of figures.
+///
+/// Figures are shuffled with a seeded generator,
+/// so the same seed always gives the same sequence of figures.
+#[derive(Debug, Clone)]
+pub struct TetroBag {
+    bag: VecDeque<FigureType>,
+    rng: StdRng,
+}
+
+impl TetroBag {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            bag: VecDeque::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Get next figure from the bag.
+    pub fn draw_next(&mut self) -> FigureType {
+        self.refill();
+        self.bag.pop_front().expect("This should not have happened")
+    }
+
+    /// Look at the upcoming figures without taking them out of the bag.
+    ///
+    /// The bag always keeps at least one full set of figures
+    /// after drawing, so up to 7 figures can be previewed.
+    pub fn peek(&self, count: usize) -> impl Iterator<Item = &FigureType> {
+        self.bag.iter().take(count)
+    }
+
+    /// Add new shuffled sets of figures until
+    /// the bag has enough figures to preview after the next draw.
+    fn refill(&mut self) {
+        while self.bag.len() <= FigureType::all().len() {
+            let mut new_figs = FigureType::all().to_vec();
+            new_figs.shuffle(&mut self.rng);
+            for fig in new_figs {
+                self.bag.push_back(fig);
+            }
+        }
+    }
+}
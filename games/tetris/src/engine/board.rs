@@ -0,0 +1,220 @@
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use super::FigureType;
+
+/// Dimensions of the game field.
+///
+/// Rows above the visible ones are a hidden buffer zone,
+/// where figures spawn and can be pushed to by garbage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoardSize {
+    pub width: usize,
+    pub height: usize,
+    pub visible: usize,
+}
+
+impl Default for BoardSize {
+    fn default() -> Self {
+        Self::GUIDELINE
+    }
+}
+
+impl BoardSize {
+    // Original field without a buffer zone.
+    pub const CLASSIC: BoardSize = BoardSize::new(10, 20, 20);
+    // Guideline matrix with 20 hidden rows.
+    pub const GUIDELINE: BoardSize = BoardSize::new(10, 40, 20);
+    pub const NARROW: BoardSize = BoardSize::new(6, 40, 20);
+    pub const WIDE: BoardSize = BoardSize::new(16, 40, 20);
+
+    pub const fn new(width: usize, height: usize, visible: usize) -> Self {
+        Self {
+            width,
+            height,
+            visible,
+        }
+    }
+
+    pub fn presets() -> [BoardSize; 4] {
+        [
+            BoardSize::GUIDELINE,
+            BoardSize::CLASSIC,
+            BoardSize::NARROW,
+            BoardSize::WIDE,
+        ]
+    }
+
+    /// Bottom left corner of the spawned figure's bounding box.
+    ///
+    /// Figures spawn at the top of the visible field,
+    /// poking into the buffer zone if there is one.
+    pub fn spawn_position(&self) -> (i32, i32) {
+        let x = self.width as i32 / 2 - 1;
+        let y = (self.visible as i32 - 1).min(self.height as i32 - 2);
+        (x, y)
+    }
+}
+
+/// Boards are shown as width x height,
+/// with the number of visible rows if some are hidden.
+impl fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)?;
+        if self.visible != self.height {
+            write!(f, "/{}", self.visible)?;
+        }
+        Ok(())
+    }
+}
+
+/// Content of a single cell of the board.
+///
+/// Locked tiles remember the figure they come from,
+/// so the stack can be drawn with figure colors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Cell {
+    #[default]
+    Empty,
+    Figure(FigureType),
+    Garbage,
+}
+
+impl Cell {
+    pub fn is_occupied(&self) -> bool {
+        *self != Cell::Empty
+    }
+}
+
+/// Game field. Rows are stored from the bottom to the top.
+#[derive(Debug, Clone)]
+pub struct GameBoard(pub Vec<Vec<Cell>>);
+
+impl Deref for GameBoard {
+    type Target = Vec<Vec<Cell>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for GameBoard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Default for GameBoard {
+    fn default() -> Self {
+        Self::new(BoardSize::default())
+    }
+}
+
+impl GameBoard {
+    pub fn new(size: BoardSize) -> Self {
+        Self(vec![vec![Cell::Empty; size.width]; size.height])
+    }
+
+    pub fn reset(&mut self) {
+        for row in self.iter_mut() {
+            for cell in row.iter_mut() {
+                *cell = Cell::Empty;
+            }
+        }
+    }
+
+    /// Check if the selected position is available.
+    /// Returns true in case if it is.
+    pub fn check(&self, pos: (i32, i32)) -> bool {
+        if pos.1 < 0 || pos.1 >= self.len() as i32 {
+            return false;
+        }
+        if pos.0 < 0 || pos.0 >= self[0].len() as i32 {
+            return false;
+        }
+        !self[pos.1 as usize][pos.0 as usize].is_occupied()
+    }
+
+    /// Content of the cell, cells outside of the board are empty.
+    pub fn cell(&self, pos: (i32, i32)) -> Cell {
+        if pos.0 < 0 || pos.1 < 0 {
+            return Cell::Empty;
+        }
+        self.get(pos.1 as usize)
+            .and_then(|row| row.get(pos.0 as usize))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn occupy(&mut self, pos: (i32, i32), cell: Cell) {
+        if pos.1 < 0 || pos.1 >= self.len() as i32 {
+            return;
+        }
+        if pos.0 < 0 || pos.0 >= self[0].len() as i32 {
+            return;
+        }
+        self[pos.1 as usize][pos.0 as usize] = cell;
+    }
+
+    /// Get the number of rows a figure can fall
+    /// before it hits the floor or other tiles.
+    ///
+    /// Every tile is swept down its column, so figures
+    /// can slide under overhangs. Used to perform HardDrop of a figure
+    /// and to draw the ghost figure.
+    pub fn drop_distance(&self, tiles: &[(i32, i32)]) -> i32 {
+        if tiles.is_empty() {
+            return 0;
+        }
+        let mut distance = 0;
+        while tiles
+            .iter()
+            .all(|(x, y)| self.check((*x, *y - distance - 1)))
+        {
+            distance += 1;
+        }
+        distance
+    }
+
+    /// Push all rows up and add a garbage row at the bottom,
+    /// filled except for the hole.
+    ///
+    /// Returns false if locked tiles were pushed out of the board.
+    pub fn push_garbage(&mut self, hole: usize) -> bool {
+        let width = self[0].len();
+        let mut row = vec![Cell::Garbage; width];
+        row[hole.min(width - 1)] = Cell::Empty;
+        self.insert(0, row);
+        let top = self.pop().unwrap_or_default();
+        top.iter().all(|x| !x.is_occupied())
+    }
+
+    /// Indices of filled rows, from the bottom to the top.
+    pub fn full_rows(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|y| self[*y].iter().all(Cell::is_occupied))
+            .collect()
+    }
+
+    /// This function is used to clear filled
+    /// lines of a game board. Plus it shifts
+    /// the lines above the cleared lines down.
+    /// It returns the number of lines cleared.
+    pub fn clear_lines(&mut self) -> usize {
+        let width = self[0].len();
+        let mut cleared = 0;
+        let mut y = self.len() as i32 - 1;
+        while y >= 0 {
+            if self[y as usize].iter().all(Cell::is_occupied) {
+                self.remove(y as usize);
+                self.push(vec![Cell::Empty; width]);
+                cleared += 1;
+            } else {
+                y -= 1;
+            }
+        }
+        cleared
+    }
+}
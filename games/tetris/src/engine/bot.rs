@@ -0,0 +1,146 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{GameBoard, Piece, ReplayInput, TetrisEngine};
+
+/// Weights of the placement heuristic.
+///
+/// Every placement is rated by the board it leaves behind,
+/// penalties have negative weights.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BotWeights {
+    // Sum of the column heights.
+    pub aggregate_height: f32,
+    pub lines: f32,
+    // Free cells with an occupied cell above them.
+    pub holes: f32,
+    // Sum of height differences between neighbouring columns.
+    pub bumpiness: f32,
+}
+
+impl Default for BotWeights {
+    fn default() -> Self {
+        Self {
+            aggregate_height: -0.51,
+            lines: 0.76,
+            holes: -0.36,
+            bumpiness: -0.18,
+        }
+    }
+}
+
+/// Best placement found by the bot
+/// and the inputs which lead to it, ending with a hard drop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub inputs: Vec<ReplayInput>,
+    pub rating: f32,
+}
+
+impl TetrisEngine {
+    /// Finds the best placement for the current figure
+    /// or for the one swapped in by holding.
+    ///
+    /// Placements are searched among all positions reachable
+    /// from the current one by moves and rotations,
+    /// so wall-kicks are taken into account.
+    pub fn plan_placement(&self, weights: &BotWeights) -> Option<Plan> {
+        let mut best: Option<Plan> = None;
+        for hold in [false, true] {
+            let mut start = self.clone();
+            let mut prefix = Vec::new();
+            if hold {
+                if start.hold() != Some(true) {
+                    continue;
+                }
+                prefix.push(ReplayInput::Hold);
+            }
+            for (piece, inputs) in reachable(&start) {
+                let mut placed = start.clone();
+                placed.piece = piece;
+                placed.hard_drop();
+                let t_spin = placed.lock();
+                let cleared = placed.clear_lines(t_spin).cleared;
+                let rating = rate(&placed.board, cleared, weights);
+                if best.as_ref().is_none_or(|best| rating > best.rating) {
+                    let mut plan_inputs = prefix.clone();
+                    plan_inputs.extend(inputs);
+                    plan_inputs.push(ReplayInput::HardDrop);
+                    best = Some(Plan {
+                        inputs: plan_inputs,
+                        rating,
+                    });
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Positions of the current figure reachable without falling,
+/// with the shortest inputs to reach each of them.
+fn reachable(engine: &TetrisEngine) -> Vec<(Piece, Vec<ReplayInput>)> {
+    const INPUTS: [ReplayInput; 4] = [
+        ReplayInput::Move { right: false },
+        ReplayInput::Move { right: true },
+        ReplayInput::Rotate { clockwise: true },
+        ReplayInput::Rotate { clockwise: false },
+    ];
+    let mut sim = engine.clone();
+    let mut visited = HashSet::from([tiles_key(&engine.piece)]);
+    let mut queue = VecDeque::from([(engine.piece.clone(), Vec::new())]);
+    let mut found = Vec::new();
+    while let Some((piece, inputs)) = queue.pop_front() {
+        for input in INPUTS {
+            sim.piece = piece.clone();
+            let moved = match input {
+                ReplayInput::Move { right } => sim.move_horizontally(right),
+                ReplayInput::Rotate { clockwise } => sim.rotate(clockwise),
+                _ => false,
+            };
+            if moved && visited.insert(tiles_key(&sim.piece)) {
+                let mut next_inputs = inputs.clone();
+                next_inputs.push(input);
+                queue.push_back((sim.piece.clone(), next_inputs));
+            }
+        }
+        found.push((piece, inputs));
+    }
+    found
+}
+
+/// Same tiles in any order, or reached by different rotations
+/// of a symmetric figure, are the same position.
+fn tiles_key(piece: &Piece) -> [(i32, i32); 4] {
+    let mut tiles = piece.tiles;
+    tiles.sort_unstable();
+    tiles
+}
+
+/// Rates the board left after a placement, the higher the better.
+fn rate(board: &GameBoard, cleared: usize, weights: &BotWeights) -> f32 {
+    let width = board[0].len();
+    let heights = (0..width)
+        .map(|x| {
+            (0..board.len())
+                .rev()
+                .find(|y| board[*y][x].is_occupied())
+                .map_or(0, |y| y + 1)
+        })
+        .collect::<Vec<_>>();
+    let holes = (0..width)
+        .map(|x| {
+            (0..heights[x])
+                .filter(|y| !board[*y][x].is_occupied())
+                .count()
+        })
+        .sum::<usize>();
+    let bumpiness = heights
+        .windows(2)
+        .map(|pair| pair[0].abs_diff(pair[1]))
+        .sum::<usize>();
+    let aggregate_height = heights.iter().sum::<usize>();
+    weights.aggregate_height * aggregate_height as f32
+        + weights.lines * cleared as f32
+        + weights.holes * holes as f32
+        + weights.bumpiness * bumpiness as f32
+}
@@ -0,0 +1,533 @@
+//! Game rules of tetris, which don't depend on bevy.
+//!
+//! Systems of the game screen only drive the engine
+//! (with timers and inputs) and render its state.
+use std::{fmt, time::Duration};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    consts,
+    utils::rotations::{rotate_left, rotate_right},
+};
+
+mod bag;
+mod board;
+mod bot;
+mod figures;
+mod mode;
+mod replay;
+mod score;
+#[cfg(test)]
+mod tests;
+
+pub use bag::TetroBag;
+pub use board::{BoardSize, Cell, GameBoard};
+pub use bot::{BotWeights, Plan};
+pub use figures::{FigureType, Rotation};
+pub use mode::{format_time, GameMode};
+pub use replay::{Replay, ReplayInput};
+pub use score::{ClearResult, Score};
+
+/// Figure put aside by the player.
+///
+/// Holding is locked after every swap
+/// and becomes available again after the next lockdown.
+#[derive(Debug, Clone, Default)]
+pub struct HoldSlot {
+    pub figure: Option<FigureType>,
+    pub locked: bool,
+}
+
+/// Information about the last successful move
+/// of the current figure. Used to detect t-spins.
+#[derive(Debug, Clone, Default)]
+pub struct LastMove {
+    pub rotated: bool,
+    // Index of the wall-kick test used for the rotation.
+    pub kick: usize,
+}
+
+/// Lockdown postponing of the current figure ("extended placement").
+///
+/// Every move or rotation of a grounded figure resets the lockdown,
+/// but only a limited number of times. The count is cleared
+/// when the figure falls below its lowest row so far.
+#[derive(Debug, Clone, Default)]
+pub struct LockResets {
+    pub count: usize,
+    pub lowest_row: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TSpin {
+    Mini,
+    Full,
+}
+
+/// Reason the game has ended for the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopOut {
+    // New figure overlaps locked tiles at the spawn position.
+    BlockOut,
+    // Figure has locked entirely above the visible field.
+    LockOut,
+    // Garbage has pushed locked tiles out of the board.
+    PushOut,
+}
+
+impl fmt::Display for TopOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TopOut::BlockOut => "BLOCK OUT",
+            TopOut::LockOut => "LOCK OUT",
+            TopOut::PushOut => "TOP OUT",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Figure controlled by the player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piece {
+    pub figure: FigureType,
+    pub rotation: Rotation,
+    pub tiles: [(i32, i32); 4],
+}
+
+impl Piece {
+    /// Figure in its spawned state at the spawn position of the board.
+    pub fn spawn(figure: FigureType, size: &BoardSize) -> Self {
+        let (x, y) = size.spawn_position();
+        Self::at(figure, x, y)
+    }
+
+    /// Figure in its spawned state,
+    /// with the bottom left corner of its bounding box at (x, y).
+    pub fn at(figure: FigureType, x: i32, y: i32) -> Self {
+        let mut tiles = [(0, 0); 4];
+        for (tile, (dot_x, dot_y)) in tiles.iter_mut().zip(figure.to_dots()) {
+            *tile = (dot_x + x, dot_y + y);
+        }
+        Self {
+            figure,
+            rotation: Rotation::R0,
+            tiles,
+        }
+    }
+
+    /// Tiles of the figure moved by the given offset.
+    pub fn shifted(&self, dx: i32, dy: i32) -> [(i32, i32); 4] {
+        self.tiles.map(|(x, y)| (x + dx, y + dy))
+    }
+
+    /// Lowest row occupied by the figure.
+    pub fn bottom(&self) -> i32 {
+        self.offset().1
+    }
+
+    /// Offsets of the figure bounding box.
+    fn offset(&self) -> (i32, i32) {
+        let x_offset = self.tiles.iter().map(|(x, _)| *x).min().unwrap_or(0);
+        let y_offset = self.tiles.iter().map(|(_, y)| *y).min().unwrap_or(0);
+        (x_offset, y_offset)
+    }
+
+    /// Absolute position of the figure's center point.
+    pub fn center(&self) -> (i32, i32) {
+        let (x_offset, y_offset) = self.offset();
+        let (center_x, center_y) = self.figure.center_point(&self.rotation);
+        (x_offset + center_x, y_offset + center_y)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TetrisEngine {
+    // Seed of the figures generator.
+    pub seed: u64,
+    pub mode: GameMode,
+    pub size: BoardSize,
+    // Time played in the current game.
+    pub elapsed: Duration,
+    pub board: GameBoard,
+    pub piece: Piece,
+    pub bag: TetroBag,
+    pub hold: HoldSlot,
+    pub score: Score,
+    pub last_move: LastMove,
+    pub lock_resets: LockResets,
+    // Time left before the grounded figure locks,
+    // None while the lockdown isn't running.
+    pub lockdown: Option<Duration>,
+    // Garbage rows which are not cleared yet.
+    // They are always at the bottom of the board.
+    pub garbage_left: usize,
+    // Generator of garbage holes, separate from the bag
+    // so garbage doesn't change the sequence of figures.
+    garbage_rng: StdRng,
+}
+
+impl Default for TetrisEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TetrisEngine {
+    /// Create a new game with a random seed.
+    pub fn new() -> Self {
+        Self::with_seed(rand::random())
+    }
+
+    /// Create a new marathon game on the default board.
+    ///
+    /// Games with the same seed get the same figures,
+    /// so they can be reproduced with the same inputs.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_options(seed, GameMode::default(), BoardSize::default())
+    }
+
+    /// Create a new game of the given mode with the first figure spawned.
+    pub fn with_options(seed: u64, mode: GameMode, size: BoardSize) -> Self {
+        let mut bag = TetroBag::new(seed);
+        let piece = Piece::spawn(bag.draw_next(), &size);
+        let lock_resets = LockResets {
+            count: 0,
+            lowest_row: piece.bottom(),
+        };
+        let mut engine = Self {
+            seed,
+            mode,
+            size,
+            elapsed: Duration::ZERO,
+            board: GameBoard::new(size),
+            piece,
+            bag,
+            hold: HoldSlot::default(),
+            score: Score::default(),
+            last_move: LastMove::default(),
+            lock_resets,
+            lockdown: None,
+            garbage_left: 0,
+            garbage_rng: StdRng::seed_from_u64(seed.wrapping_add(1)),
+        };
+        engine.add_garbage(mode.garbage_rows());
+        engine
+    }
+
+    pub fn advance_clock(&mut self, delta: Duration) {
+        self.elapsed += delta;
+    }
+
+    /// Checks if the goal of the game mode is reached.
+    pub fn goal_reached(&self) -> bool {
+        match self.mode {
+            GameMode::Marathon => false,
+            GameMode::Sprint => self.score.lines_cleared >= consts::SPRINT_LINES,
+            GameMode::Ultra => self.elapsed >= consts::ULTRA_TIME_LIMIT,
+            GameMode::Dig | GameMode::RisingTide => self.garbage_left == 0,
+        }
+    }
+
+    /// Add garbage rows with a single random hole at the bottom of the board.
+    ///
+    /// Current figure is pushed up if it overlaps new tiles.
+    /// Returns false if the board has overflowed.
+    pub fn add_garbage(&mut self, rows: usize) -> bool {
+        let fits = self.push_garbage_rows(rows);
+        let mut lift = 0;
+        while !self.fits(&self.piece.shifted(0, lift)) && lift <= rows as i32 {
+            lift += 1;
+        }
+        self.piece.tiles = self.piece.shifted(0, lift);
+        fits && self.fits(&self.piece.tiles)
+    }
+
+    /// Add garbage rows, while there is no figure in play,
+    /// e.g. after the current one is locked and before the next one spawns.
+    /// Returns false if the board has overflowed.
+    pub fn push_garbage_rows(&mut self, rows: usize) -> bool {
+        let width = self.board[0].len();
+        let mut fits = true;
+        for _ in 0..rows {
+            let hole = self.garbage_rng.gen_range(0..width);
+            fits &= self.board.push_garbage(hole);
+            self.garbage_left += 1;
+        }
+        fits
+    }
+
+    /// Check that all tiles are inside the board
+    /// and don't overlap locked tiles.
+    pub fn fits(&self, tiles: &[(i32, i32)]) -> bool {
+        tiles.iter().all(|pos| self.board.check(*pos))
+    }
+
+    /// Put a new figure at the spawn position.
+    /// Returns false if the figure overlaps locked tiles.
+    pub fn spawn(&mut self, figure: FigureType) -> bool {
+        self.piece = Piece::spawn(figure, &self.size);
+        self.last_move = LastMove::default();
+        self.lock_resets = LockResets {
+            count: 0,
+            lowest_row: self.piece.bottom(),
+        };
+        self.lockdown = None;
+        self.fits(&self.piece.tiles)
+    }
+
+    /// Spawn the next figure from the bag.
+    /// Returns false if the figure overlaps locked tiles.
+    pub fn spawn_next(&mut self) -> bool {
+        let figure = self.bag.draw_next();
+        self.spawn(figure)
+    }
+
+    /// Swaps current figure with the held one.
+    ///
+    /// If nothing is held yet, the next figure is taken from the bag.
+    /// Holding is locked until the current figure is locked down.
+    /// Returns None if the swap didn't happen, otherwise
+    /// returns false if the swapped figure overlaps locked tiles.
+    pub fn hold(&mut self) -> Option<bool> {
+        if self.hold.locked {
+            return None;
+        }
+        let figure = match self.hold.figure.replace(self.piece.figure) {
+            Some(figure) => figure,
+            None => self.bag.draw_next(),
+        };
+        self.hold.locked = true;
+        Some(self.spawn(figure))
+    }
+
+    pub fn move_horizontally(&mut self, right: bool) -> bool {
+        let delta = if right { 1 } else { -1 };
+        let tiles = self.piece.shifted(delta, 0);
+        if !self.fits(&tiles) {
+            return false;
+        }
+        self.piece.tiles = tiles;
+        self.last_move.rotated = false;
+        true
+    }
+
+    /// Number of cells the figure can move to the side
+    /// before it hits a wall or locked tiles.
+    pub fn horizontal_room(&self, right: bool) -> u32 {
+        let delta = if right { 1 } else { -1 };
+        let mut room = 0;
+        while self.fits(&self.piece.shifted(delta * (room as i32 + 1), 0)) {
+            room += 1;
+        }
+        room
+    }
+
+    pub fn can_move_down(&self) -> bool {
+        self.fits(&self.piece.shifted(0, -1))
+    }
+
+    /// Move the figure one row down, if possible.
+    /// Every row of a soft drop gives points.
+    pub fn step_down(&mut self, soft_drop: bool) -> bool {
+        if !self.can_move_down() {
+            return false;
+        }
+        self.piece.tiles = self.piece.shifted(0, -1);
+        self.last_move.rotated = false;
+        self.track_lowest_row();
+        if soft_drop {
+            self.score.score += self.score.level;
+        }
+        true
+    }
+
+    /// Clears the lock reset count when the figure reaches a new lowest row.
+    fn track_lowest_row(&mut self) {
+        let bottom = self.piece.bottom();
+        if bottom < self.lock_resets.lowest_row {
+            self.lock_resets = LockResets {
+                count: 0,
+                lowest_row: bottom,
+            };
+        }
+    }
+
+    /// Starts the lockdown of the grounded figure, unless it's running already.
+    pub fn start_lockdown(&mut self) {
+        self.lockdown.get_or_insert(consts::LOCK_DELAY);
+    }
+
+    /// Stops the lockdown, when the figure can fall again.
+    pub fn cancel_lockdown(&mut self) {
+        self.lockdown = None;
+    }
+
+    /// Counts down the lockdown.
+    /// Returns true when the delay has just run out.
+    pub fn tick_lockdown(&mut self, delta: Duration) -> bool {
+        let Some(left) = self.lockdown else {
+            return false;
+        };
+        if left > delta {
+            self.lockdown = Some(left - delta);
+            return false;
+        }
+        self.lockdown = None;
+        true
+    }
+
+    /// Restarts the running lockdown with one of the resets.
+    /// Returns false if the lockdown isn't running or there are
+    /// no resets left, then the figure locks when the delay runs out.
+    pub fn use_lock_reset(&mut self) -> bool {
+        if self.lockdown.is_none() || self.lock_resets_exhausted() {
+            return false;
+        }
+        self.lock_resets.count += 1;
+        self.lockdown = Some(consts::LOCK_DELAY);
+        true
+    }
+
+    pub fn lock_resets_exhausted(&self) -> bool {
+        self.lock_resets.count >= consts::MAX_LOCK_RESETS
+    }
+
+    /// Tiles of the figure at the position where it would land.
+    pub fn ghost(&self) -> [(i32, i32); 4] {
+        let distance = self.board.drop_distance(&self.piece.tiles);
+        self.piece.shifted(0, -distance)
+    }
+
+    /// Move the figure down as far as possible.
+    /// Returns the number of rows the figure has fallen.
+    pub fn hard_drop(&mut self) -> i32 {
+        let distance = self.board.drop_distance(&self.piece.tiles);
+        if distance > 0 {
+            self.piece.tiles = self.piece.shifted(0, -distance);
+            self.last_move.rotated = false;
+            self.track_lowest_row();
+            let grid_delta = distance as usize * self.piece.tiles.len();
+            self.score.score += grid_delta * 3 * self.score.level;
+        }
+        distance
+    }
+
+    /// Rotate the figure using wall-kicks.
+    ///
+    /// Returns true if any of the wall-kick tests has passed
+    /// and the figure was rotated.
+    pub fn rotate(&mut self, clockwise: bool) -> bool {
+        let piece = &self.piece;
+        let (x_offset, y_offset) = piece.offset();
+        let next_rot = if clockwise {
+            piece.rotation.right()
+        } else {
+            piece.rotation.left()
+        };
+        let (center_x, center_y) = piece.figure.center_point(&piece.rotation);
+        // Here we subtract the offset of the bounding box and the center point
+        // to get the relative position of the tile to the center point.
+        // After rotation we add them back to get the absolute position of the tile.
+        let rotated = piece.tiles.map(|(x, y)| {
+            let (new_x, new_y) = if clockwise {
+                rotate_right(x - x_offset - center_x, y - y_offset - center_y)
+            } else {
+                rotate_left(x - x_offset - center_x, y - y_offset - center_y)
+            };
+            (new_x + x_offset + center_x, new_y + y_offset + center_y)
+        });
+        // Here we iterate over rotation tests
+        // and try to fit the piece in.
+        let tests = piece.figure.tests_for_rot(&piece.rotation, &next_rot);
+        for (kick, (test_x, test_y)) in tests.into_iter().enumerate() {
+            let tiles = rotated.map(|(x, y)| (x + test_x, y + test_y));
+            if self.fits(&tiles) {
+                self.piece.tiles = tiles;
+                self.piece.rotation = next_rot;
+                self.last_move = LastMove {
+                    rotated: true,
+                    kick,
+                };
+                // Kicks can move the figure down to a new lowest row.
+                self.track_lowest_row();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Checks if the current figure is a t-spin, using the 3-corner rule.
+    ///
+    /// The last move of a T figure should be a rotation and at least
+    /// 3 of 4 corners around its center must be occupied. If one of
+    /// the two corners the figure points to is free, it's a mini t-spin,
+    /// unless the last wall-kick test was used.
+    pub fn detect_t_spin(&self) -> Option<TSpin> {
+        if self.piece.figure != FigureType::T || !self.last_move.rotated {
+            return None;
+        }
+        let (x, y) = self.piece.center();
+        let occupied = |dx: i32, dy: i32| !self.board.check((x + dx, y + dy));
+        let corners = [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+            .into_iter()
+            .filter(|(dx, dy)| occupied(*dx, *dy))
+            .count();
+        if corners < 3 {
+            return None;
+        }
+        // Corners next to the pointing side of the figure.
+        let front = match self.piece.rotation {
+            Rotation::R0 => [(-1, 1), (1, 1)],
+            Rotation::RR => [(1, 1), (1, -1)],
+            Rotation::R2 => [(-1, -1), (1, -1)],
+            Rotation::RL => [(-1, 1), (-1, -1)],
+        };
+        let last_kick = self
+            .piece
+            .figure
+            .tests_for_single_rot(&self.piece.rotation)
+            .len()
+            - 1;
+        if front.into_iter().all(|(dx, dy)| occupied(dx, dy)) || self.last_move.kick == last_kick {
+            Some(TSpin::Full)
+        } else {
+            Some(TSpin::Mini)
+        }
+    }
+
+    /// Checks if the current figure would lock
+    /// without any of its tiles in the visible field.
+    pub fn locked_out(&self) -> bool {
+        let visible = self.size.visible as i32;
+        self.piece.tiles.iter().all(|(_, y)| *y >= visible)
+    }
+
+    /// Lock the current figure in place.
+    ///
+    /// Returns the t-spin performed by the figure, if any.
+    /// Lines are not cleared here, see `clear_lines`.
+    pub fn lock(&mut self) -> Option<TSpin> {
+        let t_spin = self.detect_t_spin();
+        for pos in self.piece.tiles {
+            self.board.occupy(pos, Cell::Figure(self.piece.figure));
+        }
+        // After the lockdown player is able to hold again.
+        self.hold.locked = false;
+        self.last_move = LastMove::default();
+        t_spin
+    }
+
+    /// Clear filled lines and award points for them.
+    pub fn clear_lines(&mut self, t_spin: Option<TSpin>) -> ClearResult {
+        let garbage_cleared = self
+            .board
+            .full_rows()
+            .into_iter()
+            .filter(|y| *y < self.garbage_left)
+            .count();
+        self.garbage_left -= garbage_cleared;
+        let cleared = self.board.clear_lines();
+        self.score.award_clear(cleared, t_spin)
+    }
+}
@@ -0,0 +1,82 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::consts;
+
+/// Rules deciding when the game ends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    // Endless game, which ends only on top out.
+    #[default]
+    Marathon,
+    // Clear 40 lines as fast as possible.
+    Sprint,
+    // Score as many points as possible in 2 minutes.
+    Ultra,
+    // Clear all garbage rows as fast as possible.
+    Dig,
+    // Dig, but new garbage rows keep rising from the bottom.
+    RisingTide,
+}
+
+impl GameMode {
+    pub fn all() -> [GameMode; 5] {
+        [
+            GameMode::Marathon,
+            GameMode::Sprint,
+            GameMode::Ultra,
+            GameMode::Dig,
+            GameMode::RisingTide,
+        ]
+    }
+
+    /// Number of garbage rows the board starts with.
+    pub fn garbage_rows(&self) -> usize {
+        match self {
+            GameMode::Dig | GameMode::RisingTide => consts::DIG_GARBAGE_ROWS,
+            GameMode::Marathon | GameMode::Sprint | GameMode::Ultra => 0,
+        }
+    }
+
+    /// Time left for modes with a time limit.
+    pub fn time_left(&self, elapsed: Duration) -> Option<Duration> {
+        match self {
+            GameMode::Ultra => Some(consts::ULTRA_TIME_LIMIT.saturating_sub(elapsed)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GameMode::Marathon => "MARATHON",
+            GameMode::Sprint => "SPRINT",
+            GameMode::Ultra => "ULTRA",
+            GameMode::Dig => "DIG",
+            GameMode::RisingTide => "RISING TIDE",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GameMode::all()
+            .into_iter()
+            .find(|mode| mode.to_string().replace(' ', "-").eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown mode: {s}"))
+    }
+}
+
+/// Formats time as minutes, seconds and hundredths.
+pub fn format_time(time: Duration) -> String {
+    let hundredths = time.as_millis() / 10;
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
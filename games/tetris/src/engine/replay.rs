@@ -0,0 +1,189 @@
+use std::{fmt, str::FromStr};
+
+use crate::consts;
+
+use super::{BoardSize, GameMode};
+
+/// Single action of a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayInput {
+    Move { right: bool },
+    Rotate { clockwise: bool },
+    HardDrop,
+    Hold,
+    SoftDrop { active: bool },
+}
+
+/// Recorded game, which can be played back.
+///
+/// Inputs are stored along with the number of the game tick
+/// they were applied at and the index of the player.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: u64,
+    pub mode: GameMode,
+    pub size: BoardSize,
+    pub players: usize,
+    pub inputs: Vec<(u64, usize, ReplayInput)>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, mode: GameMode, size: BoardSize, players: usize) -> Self {
+        Self {
+            seed,
+            mode,
+            size,
+            players,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, tick: u64, player: usize, input: ReplayInput) {
+        self.inputs.push((tick, player, input));
+    }
+
+    /// Inputs applied at the given tick with their players,
+    /// in the recorded order.
+    pub fn inputs_at(&self, tick: u64) -> impl Iterator<Item = (usize, &ReplayInput)> {
+        let start = self.inputs.partition_point(|(t, _, _)| *t < tick);
+        self.inputs[start..]
+            .iter()
+            .take_while(move |(t, _, _)| *t == tick)
+            .map(|(_, player, input)| (*player, input))
+    }
+}
+
+impl fmt::Display for ReplayInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ReplayInput::Move { right: true } => "right",
+            ReplayInput::Move { right: false } => "left",
+            ReplayInput::Rotate { clockwise: true } => "cw",
+            ReplayInput::Rotate { clockwise: false } => "ccw",
+            ReplayInput::HardDrop => "drop",
+            ReplayInput::Hold => "hold",
+            ReplayInput::SoftDrop { active: true } => "soft-on",
+            ReplayInput::SoftDrop { active: false } => "soft-off",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ReplayInput {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "right" => Ok(ReplayInput::Move { right: true }),
+            "left" => Ok(ReplayInput::Move { right: false }),
+            "cw" => Ok(ReplayInput::Rotate { clockwise: true }),
+            "ccw" => Ok(ReplayInput::Rotate { clockwise: false }),
+            "drop" => Ok(ReplayInput::HardDrop),
+            "hold" => Ok(ReplayInput::Hold),
+            "soft-on" => Ok(ReplayInput::SoftDrop { active: true }),
+            "soft-off" => Ok(ReplayInput::SoftDrop { active: false }),
+            _ => Err(format!("Unknown input: {s}")),
+        }
+    }
+}
+
+/// Replays are stored as text. The first lines contain the seed,
+/// the game mode, the board size (width, height and visible rows)
+/// and the number of players. Every other line is a tick
+/// followed by the player index and an input:
+///
+/// ```text
+/// seed 42
+/// mode sprint
+/// board 10 40 20
+/// players 1
+/// 10 0 left
+/// 25 0 drop
+/// ```
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        let mode = self.mode.to_string().replace(' ', "-").to_lowercase();
+        writeln!(f, "mode {mode}")?;
+        let size = &self.size;
+        writeln!(f, "board {} {} {}", size.width, size.height, size.visible)?;
+        writeln!(f, "players {}", self.players)?;
+        for (tick, player, input) in &self.inputs {
+            writeln!(f, "{tick} {player} {input}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().filter(|line| !line.trim().is_empty());
+        let seed = lines
+            .next()
+            .and_then(|line| line.trim().strip_prefix("seed "))
+            .ok_or_else(|| String::from("Replay should start with a seed"))?
+            .parse::<u64>()
+            .map_err(|err| format!("Invalid seed: {err}"))?;
+        let mode = lines
+            .next()
+            .and_then(|line| line.trim().strip_prefix("mode "))
+            .ok_or_else(|| String::from("Replay should have a game mode after the seed"))?
+            .parse::<GameMode>()?;
+        let size = lines
+            .next()
+            .and_then(|line| line.trim().strip_prefix("board "))
+            .ok_or_else(|| String::from("Replay should have a board size after the mode"))?
+            .split(' ')
+            .map(|value| value.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("Invalid board size: {err}"))?;
+        let &[width, height, visible] = size.as_slice() else {
+            return Err(String::from("Board size should have 3 values"));
+        };
+        // Figures should fit the board, and there can't be
+        // more visible rows than there are rows.
+        if width < 4 || visible < 4 || visible > height {
+            return Err(format!(
+                "Unsupported board size: {width} {height} {visible}"
+            ));
+        }
+        let size = BoardSize::new(width, height, visible);
+        let players = lines
+            .next()
+            .and_then(|line| line.trim().strip_prefix("players "))
+            .ok_or_else(|| String::from("Replay should have a number of players after the board"))?
+            .parse::<usize>()
+            .map_err(|err| format!("Invalid number of players: {err}"))?;
+        if !(1..=consts::MAX_PLAYERS).contains(&players) {
+            return Err(format!("Unsupported number of players: {players}"));
+        }
+        let mut replay = Replay::new(seed, mode, size, players);
+        for line in lines {
+            let mut parts = line.split_whitespace();
+            let (Some(tick), Some(player), Some(input), None) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                return Err(format!("Invalid line: {line}"));
+            };
+            let tick = tick
+                .parse::<u64>()
+                .map_err(|err| format!("Invalid tick: {err}"))?;
+            let player = player
+                .parse::<usize>()
+                .ok()
+                .filter(|player| *player < players)
+                .ok_or_else(|| format!("Invalid player: {line}"))?;
+            if replay
+                .inputs
+                .last()
+                .is_some_and(|(last, _, _)| *last > tick)
+            {
+                return Err(format!("Ticks are not in order: {line}"));
+            }
+            replay.push(tick, player, input.parse()?);
+        }
+        Ok(replay)
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use crate::consts;
+
+use super::TSpin;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Score {
+    pub score: usize,
+    pub lines_cleared: usize,
+    pub level: usize,
+    // Number of consecutive line clears after the first one.
+    // None means that the last figure didn't clear any lines.
+    pub combo: Option<usize>,
+    // Whether the last line clear was a difficult one (tetris or t-spin).
+    pub back_to_back: bool,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            score: 0,
+            lines_cleared: 0,
+            level: 1,
+            combo: None,
+            back_to_back: false,
+        }
+    }
+}
+
+/// Outcome of placing a figure on the board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClearResult {
+    pub cleared: usize,
+    pub t_spin: Option<TSpin>,
+    pub back_to_back: bool,
+    pub combo: usize,
+    pub points: usize,
+}
+
+impl ClearResult {
+    /// Garbage rows sent to the opponent in versus games.
+    ///
+    /// T-spins send two rows per cleared line
+    /// and back-to-back clears send one more row.
+    pub fn garbage(&self) -> usize {
+        if self.cleared == 0 {
+            return 0;
+        }
+        let rows = match self.t_spin {
+            Some(TSpin::Full) => self.cleared * 2,
+            Some(TSpin::Mini) | None => consts::GARBAGE_LINES[self.cleared.min(4) - 1],
+        };
+        rows + usize::from(self.back_to_back)
+    }
+}
+
+impl Score {
+    /// Add cleared lines and raise the level
+    /// every `LINES_PER_LEVEL` lines.
+    pub fn add_lines(&mut self, lines: usize) {
+        self.lines_cleared += lines;
+        self.level = 1 + self.lines_cleared / consts::LINES_PER_LEVEL;
+    }
+
+    /// Time it takes for a figure to fall one row
+    /// on the current level.
+    ///
+    /// This is the speed curve from the tetris guideline:
+    /// (0.8 - (level - 1) * 0.007) ^ (level - 1) seconds.
+    pub fn gravity_interval(&self) -> Duration {
+        let level = self.level.clamp(1, consts::MAX_SPEED_LEVEL) as f64 - 1.;
+        Duration::from_secs_f64((0.8 - level * 0.007).powf(level))
+    }
+
+    /// Award points for a placed figure.
+    ///
+    /// Besides regular clears, t-spins, combos and back-to-back
+    /// difficult clears (tetrises and t-spins) give bonus points.
+    pub fn award_clear(&mut self, cleared: usize, t_spin: Option<TSpin>) -> ClearResult {
+        // Points are calculated with the level before clearing,
+        // as in the guideline.
+        let mut points = match (t_spin, cleared) {
+            (None, 0) => 0,
+            (None, n) => consts::LINE_CLEAR_POINTS[n.min(4) - 1],
+            (Some(TSpin::Mini), n) => consts::T_SPIN_MINI_POINTS[n.min(2)],
+            (Some(TSpin::Full), n) => consts::T_SPIN_POINTS[n.min(3)],
+        };
+        let difficult = cleared == 4 || (cleared > 0 && t_spin.is_some());
+        let back_to_back = difficult && self.back_to_back;
+        if back_to_back {
+            points += points / 2;
+        }
+        if cleared > 0 {
+            self.combo = Some(self.combo.map_or(0, |combo| combo + 1));
+            self.back_to_back = difficult;
+        } else {
+            self.combo = None;
+        }
+        let combo = self.combo.unwrap_or(0);
+        points += consts::COMBO_POINTS * combo;
+        points *= self.level;
+        self.score += points;
+        self.add_lines(cleared);
+        ClearResult {
+            cleared,
+            t_spin,
+            back_to_back,
+            combo,
+            points,
+        }
+    }
+}
@@ -0,0 +1,783 @@
+use std::collections::HashSet;
+
+use super::*;
+
+use Rotation::{R0, R2, RL, RR};
+
+type Kicks = [(Rotation, bool, [(i32, i32); 5]); 8];
+
+/// Wall-kick data from the SRS specification.
+///
+/// Every entry is (rotation before, clockwise, kicks),
+/// positive y goes up, the same as on our board.
+const JLSTZ_KICKS: Kicks = [
+    (R0, true, [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]),
+    (RR, false, [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)]),
+    (RR, true, [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)]),
+    (R2, false, [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]),
+    (R2, true, [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)]),
+    (RL, false, [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)]),
+    (RL, true, [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)]),
+    (R0, false, [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)]),
+];
+
+const I_KICKS: Kicks = [
+    (R0, true, [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)]),
+    (RR, false, [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)]),
+    (RR, true, [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)]),
+    (R2, false, [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)]),
+    (R2, true, [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)]),
+    (RL, false, [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)]),
+    (RL, true, [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)]),
+    (R0, false, [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)]),
+];
+
+/// Engine with an empty board and the given figure
+/// rotated to the requested state in the middle of the board.
+fn engine_with(figure: FigureType, rotation: Rotation) -> TetrisEngine {
+    let mut engine = TetrisEngine::new();
+    engine.piece = Piece::at(figure, 3, 8);
+    while engine.piece.rotation != rotation {
+        assert!(engine.rotate(true));
+    }
+    engine
+}
+
+/// Occupy every cell of the board, except for the given ones.
+fn fill_except(board: &mut GameBoard, free: &HashSet<(i32, i32)>) {
+    for y in 0..board.len() as i32 {
+        for x in 0..board[0].len() as i32 {
+            if !free.contains(&(x, y)) {
+                board.occupy((x, y), Cell::Garbage);
+            }
+        }
+    }
+}
+
+fn as_set(tiles: &[(i32, i32)]) -> HashSet<(i32, i32)> {
+    tiles.iter().copied().collect()
+}
+
+/// Fill the given rows of the board completely.
+fn fill_rows(board: &mut GameBoard, rows: impl IntoIterator<Item = i32>) {
+    for y in rows {
+        for x in 0..board[0].len() as i32 {
+            board.occupy((x, y), Cell::Garbage);
+        }
+    }
+}
+
+fn check_kicks(figure: FigureType, kicks: &Kicks) {
+    for (rotation, clockwise, tests) in kicks {
+        // Position of the figure rotated without any obstacles.
+        let mut open = engine_with(figure, *rotation);
+        assert!(open.rotate(*clockwise));
+        let basic = open.piece.tiles;
+        let kicked = |kick: usize| {
+            let (kick_x, kick_y) = tests[kick];
+            basic.map(|(x, y)| (x + kick_x, y + kick_y))
+        };
+        for kick in 0..tests.len() {
+            let mut engine = engine_with(figure, *rotation);
+            // The only free cells are the ones of the figure itself
+            // and the ones where it should end up after the kick.
+            let mut free = as_set(&engine.piece.tiles);
+            free.extend(kicked(kick));
+            fill_except(&mut engine.board, &free);
+            // Some kicks are unreachable, because one of the previous
+            // tests always fits in the same space. Tests are tried in order,
+            // so the first fitting one should be used.
+            let expected = (0..=kick)
+                .find(|test| kicked(*test).iter().all(|pos| free.contains(pos)))
+                .unwrap();
+
+            assert!(
+                engine.rotate(*clockwise),
+                "{figure:?} {rotation:?} clockwise={clockwise} kick #{kick} failed"
+            );
+            assert_eq!(
+                as_set(&engine.piece.tiles),
+                as_set(&kicked(expected)),
+                "{figure:?} {rotation:?} clockwise={clockwise} kick #{kick}"
+            );
+            assert_eq!(engine.last_move.kick, expected);
+            assert!(engine.last_move.rotated);
+        }
+    }
+}
+
+#[test]
+fn kick_offsets_match_srs_tables() {
+    for figure in [
+        FigureType::J,
+        FigureType::L,
+        FigureType::S,
+        FigureType::T,
+        FigureType::Z,
+    ] {
+        for (rotation, clockwise, tests) in JLSTZ_KICKS {
+            let next = if clockwise {
+                rotation.right()
+            } else {
+                rotation.left()
+            };
+            let offsets = figure.tests_for_rot(&rotation, &next);
+            assert_eq!(offsets, tests.to_vec(), "{figure:?} {rotation:?}");
+        }
+    }
+    // Offsets of I figure also contain a shift to keep it
+    // in the right place, so we compare them relative to the first test.
+    for (rotation, clockwise, tests) in I_KICKS {
+        let next = if clockwise {
+            rotation.right()
+        } else {
+            rotation.left()
+        };
+        let offsets = FigureType::I.tests_for_rot(&rotation, &next);
+        let (base_x, base_y) = offsets[0];
+        let relative = offsets
+            .iter()
+            .map(|(x, y)| (x - base_x, y - base_y))
+            .collect::<Vec<_>>();
+        assert_eq!(relative, tests.to_vec(), "I {rotation:?}");
+    }
+}
+
+#[test]
+fn jlstz_kicks() {
+    for figure in [
+        FigureType::J,
+        FigureType::L,
+        FigureType::S,
+        FigureType::T,
+        FigureType::Z,
+    ] {
+        check_kicks(figure, &JLSTZ_KICKS);
+    }
+}
+
+#[test]
+fn i_kicks() {
+    check_kicks(FigureType::I, &I_KICKS);
+}
+
+#[test]
+fn i_rotation_follows_srs_states() {
+    // In SRS, I figure in a 4x4 box occupies the third row in the spawn state,
+    // third column in R state, second row in 2 state and second column in L state.
+    let mut engine = TetrisEngine::new();
+    engine.piece = Piece::at(FigureType::I, 0, 2);
+    assert!(engine.rotate(true));
+    assert_eq!(
+        as_set(&engine.piece.tiles),
+        as_set(&[(2, 0), (2, 1), (2, 2), (2, 3)])
+    );
+    assert!(engine.rotate(true));
+    assert_eq!(
+        as_set(&engine.piece.tiles),
+        as_set(&[(0, 1), (1, 1), (2, 1), (3, 1)])
+    );
+    assert!(engine.rotate(true));
+    assert_eq!(
+        as_set(&engine.piece.tiles),
+        as_set(&[(1, 0), (1, 1), (1, 2), (1, 3)])
+    );
+    assert!(engine.rotate(true));
+    assert_eq!(
+        as_set(&engine.piece.tiles),
+        as_set(&[(0, 2), (1, 2), (2, 2), (3, 2)])
+    );
+}
+
+#[test]
+fn o_rotates_in_place() {
+    for rotation in [R0, RR, R2, RL] {
+        for clockwise in [true, false] {
+            let mut engine = engine_with(FigureType::O, rotation);
+            let before = as_set(&engine.piece.tiles);
+            assert!(engine.rotate(clockwise));
+            assert_eq!(as_set(&engine.piece.tiles), before);
+        }
+    }
+}
+
+#[test]
+fn full_turn_returns_figure_back() {
+    for figure in FigureType::all() {
+        for clockwise in [true, false] {
+            let mut engine = engine_with(*figure, R0);
+            let before = as_set(&engine.piece.tiles);
+            for _ in 0..4 {
+                assert!(engine.rotate(clockwise));
+            }
+            assert_eq!(engine.piece.rotation, R0);
+            assert_eq!(as_set(&engine.piece.tiles), before, "{figure:?}");
+        }
+    }
+}
+
+#[test]
+fn rotation_fails_without_space() {
+    let mut engine = engine_with(FigureType::T, R0);
+    let before = engine.piece.clone();
+    fill_except(&mut engine.board, &as_set(&before.tiles));
+    assert!(!engine.rotate(true));
+    assert!(!engine.rotate(false));
+    assert_eq!(engine.piece, before);
+}
+
+#[test]
+fn gravity_step_and_hard_drop() {
+    let mut engine = TetrisEngine::new();
+    engine.piece = Piece::at(FigureType::O, 0, 5);
+    assert!(engine.step_down(false));
+    assert_eq!(
+        as_set(&engine.piece.tiles),
+        as_set(&[(0, 4), (1, 4), (0, 5), (1, 5)])
+    );
+    assert_eq!(engine.score.score, 0);
+    // Soft drop gives a point for every row.
+    assert!(engine.step_down(true));
+    assert_eq!(engine.score.score, 1);
+    assert_eq!(engine.hard_drop(), 3);
+    assert!(!engine.can_move_down());
+    assert!(!engine.step_down(false));
+    assert_eq!(
+        as_set(&engine.piece.tiles),
+        as_set(&[(0, 0), (1, 0), (0, 1), (1, 1)])
+    );
+}
+
+#[test]
+fn hard_drop_slides_under_overhang() {
+    let mut engine = TetrisEngine::new();
+    // Overhang above the column where the figure falls.
+    engine.board.occupy((0, 3), Cell::Garbage);
+    engine.board.occupy((0, 0), Cell::Garbage);
+    engine.piece = Piece::at(FigureType::I, 1, 10);
+    assert_eq!(engine.hard_drop(), 10);
+    assert_eq!(engine.ghost(), engine.piece.tiles);
+}
+
+#[test]
+fn horizontal_room_stops_at_walls_and_tiles() {
+    let mut engine = TetrisEngine::new();
+    engine.piece = Piece::at(FigureType::O, 0, 5);
+    assert_eq!(engine.horizontal_room(false), 0);
+    assert_eq!(engine.horizontal_room(true), 8);
+    engine.board.occupy((6, 6), Cell::Garbage);
+    assert_eq!(engine.horizontal_room(true), 4);
+    for _ in 0..4 {
+        assert!(engine.move_horizontally(true));
+    }
+    assert_eq!(engine.horizontal_room(true), 0);
+    assert!(!engine.move_horizontally(true));
+}
+
+#[test]
+fn single_line_clear() {
+    let mut engine = TetrisEngine::new();
+    for x in 4..10 {
+        engine.board.occupy((x, 0), Cell::Garbage);
+    }
+    engine.board.occupy((9, 1), Cell::Garbage);
+    engine.piece = Piece::at(FigureType::I, 0, 0);
+    assert_eq!(engine.lock(), None);
+    assert_eq!(engine.board.full_rows(), [0]);
+    let result = engine.clear_lines(None);
+    assert_eq!(result.cleared, 1);
+    assert_eq!(result.points, 100);
+    assert_eq!(engine.score.score, 100);
+    assert_eq!(engine.score.lines_cleared, 1);
+    // Row above the cleared line moves down.
+    assert!(!engine.board.check((9, 0)));
+    assert!((0..9).all(|x| engine.board.check((x, 0))));
+    assert!(engine
+        .board
+        .iter()
+        .skip(1)
+        .flatten()
+        .all(|cell| !cell.is_occupied()));
+}
+
+#[test]
+fn separated_lines_clear() {
+    let mut engine = TetrisEngine::new();
+    fill_rows(&mut engine.board, [0, 2]);
+    engine.board.occupy((5, 1), Cell::Garbage);
+    engine.board.occupy((6, 3), Cell::Garbage);
+    assert_eq!(engine.board.clear_lines(), 2);
+    assert!(!engine.board.check((5, 0)));
+    assert!(!engine.board.check((6, 1)));
+    assert_eq!(
+        engine
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| cell.is_occupied())
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn tetris_back_to_back_and_combo() {
+    let mut score = Score::default();
+    let first = score.award_clear(4, None);
+    assert_eq!(first.points, 800);
+    assert!(!first.back_to_back);
+    assert_eq!(first.combo, 0);
+
+    // Second tetris in a row is back-to-back and continues the combo.
+    let second = score.award_clear(4, None);
+    assert!(second.back_to_back);
+    assert_eq!(second.combo, 1);
+    assert_eq!(second.points, 1200 + 50);
+
+    // Regular clear breaks back-to-back, but not the combo.
+    let third = score.award_clear(1, None);
+    assert!(!third.back_to_back);
+    assert_eq!(third.combo, 2);
+    assert_eq!(third.points, 100 + 100);
+    assert!(!score.back_to_back);
+
+    // Placing a figure without clearing lines breaks the combo.
+    let fourth = score.award_clear(0, None);
+    assert_eq!(fourth.points, 0);
+    assert_eq!(score.combo, None);
+    assert_eq!(score.score, 800 + 1250 + 200);
+}
+
+#[test]
+fn level_raises_every_ten_lines() {
+    let mut score = Score::default();
+    let initial_interval = score.gravity_interval();
+    assert_eq!(score.level, 1);
+    score.add_lines(9);
+    assert_eq!(score.level, 1);
+    score.add_lines(1);
+    assert_eq!(score.level, 2);
+    assert!(score.gravity_interval() < initial_interval);
+    // Points are multiplied by the level.
+    let result = score.award_clear(1, None);
+    assert_eq!(result.points, 200);
+}
+
+#[test]
+fn t_spin_double() {
+    let mut engine = TetrisEngine::new();
+    // Two bottom rows have a T-shaped hole,
+    // covered by an overhang on the left.
+    fill_rows(&mut engine.board, [0, 1]);
+    engine.board[0][4] = Cell::Empty;
+    engine.board[1][3] = Cell::Empty;
+    engine.board[1][4] = Cell::Empty;
+    engine.board[1][5] = Cell::Empty;
+    engine.board.occupy((3, 2), Cell::Garbage);
+    let mut dropped = engine.clone();
+    engine.piece = Piece {
+        figure: FigureType::T,
+        rotation: RR,
+        tiles: [(4, 0), (4, 1), (4, 2), (5, 1)],
+    };
+    assert!(engine.rotate(true));
+    assert_eq!(engine.piece.rotation, R2);
+    assert_eq!(engine.lock(), Some(TSpin::Full));
+    let result = engine.clear_lines(Some(TSpin::Full));
+    assert_eq!(result.cleared, 2);
+    assert_eq!(result.points, 1200);
+
+    // The same placement without a rotation is not a t-spin.
+    dropped.piece = Piece {
+        figure: FigureType::T,
+        rotation: R2,
+        tiles: [(3, 1), (4, 1), (5, 1), (4, 0)],
+    };
+    assert_eq!(dropped.lock(), None);
+}
+
+#[test]
+fn t_spin_mini() {
+    let mut engine = TetrisEngine::new();
+    // First two kicks are blocked, so the figure
+    // is kicked down to the floor, pointing up.
+    engine.board.occupy((3, 1), Cell::Garbage);
+    engine.board.occupy((6, 1), Cell::Garbage);
+    engine.piece = Piece {
+        figure: FigureType::T,
+        rotation: RR,
+        tiles: [(4, 0), (4, 1), (4, 2), (5, 1)],
+    };
+    assert!(engine.rotate(false));
+    assert_eq!(engine.piece.rotation, R0);
+    assert_eq!(engine.last_move.kick, 2);
+    assert_eq!(
+        as_set(&engine.piece.tiles),
+        as_set(&[(4, 0), (5, 0), (6, 0), (5, 1)])
+    );
+    assert_eq!(engine.lock(), Some(TSpin::Mini));
+}
+
+#[test]
+fn hold_is_locked_until_lockdown() {
+    let mut engine = TetrisEngine::new();
+    let first = engine.piece.figure;
+    assert_eq!(engine.hold(), Some(true));
+    assert_eq!(engine.hold.figure, Some(first));
+    assert_eq!(engine.hold(), None);
+    engine.hard_drop();
+    engine.lock();
+    assert!(engine.spawn_next());
+    let current = engine.piece.figure;
+    assert_eq!(engine.hold(), Some(true));
+    assert_eq!(engine.piece.figure, first);
+    assert_eq!(engine.hold.figure, Some(current));
+    assert_eq!(engine.piece, Piece::spawn(first, &engine.size));
+}
+
+#[test]
+fn top_out_when_spawn_is_blocked() {
+    let mut engine = TetrisEngine::new();
+    assert!(engine.spawn_next());
+    fill_rows(&mut engine.board, [engine.size.spawn_position().1]);
+    assert!(!engine.spawn_next());
+}
+
+#[test]
+fn lock_out_above_visible_field() {
+    let mut engine = TetrisEngine::with_seed(1);
+    let visible = engine.size.visible as i32;
+    engine.piece = Piece::at(FigureType::O, 4, visible - 1);
+    assert!(!engine.locked_out());
+    engine.piece = Piece::at(FigureType::O, 4, visible);
+    assert!(engine.locked_out());
+
+    // Classic board has no buffer zone to lock in.
+    let mut engine = TetrisEngine::with_options(1, GameMode::Marathon, BoardSize::CLASSIC);
+    engine.hard_drop();
+    assert!(!engine.locked_out());
+}
+
+#[test]
+fn lock_resets_are_limited_until_new_lowest_row() {
+    let mut engine = TetrisEngine::new();
+    engine.spawn(FigureType::T);
+    // Resets are only used while the lockdown is running.
+    assert!(!engine.use_lock_reset());
+    engine.start_lockdown();
+    for _ in 0..consts::MAX_LOCK_RESETS {
+        assert!(engine.use_lock_reset());
+    }
+    assert!(!engine.use_lock_reset());
+    assert!(engine.lock_resets_exhausted());
+
+    // Moving sideways doesn't give resets back.
+    assert!(engine.move_horizontally(true));
+    assert!(engine.lock_resets_exhausted());
+
+    assert!(engine.step_down(false));
+    assert!(!engine.lock_resets_exhausted());
+    assert!(engine.use_lock_reset());
+
+    // Next figure starts with all resets.
+    engine.spawn_next();
+    assert_eq!(engine.lock_resets.count, 0);
+    assert_eq!(engine.lockdown, None);
+}
+
+#[test]
+fn last_lock_reset_waits_the_full_delay() {
+    let mut engine = TetrisEngine::new();
+    engine.spawn(FigureType::T);
+    engine.hard_drop();
+    engine.start_lockdown();
+    let half = consts::LOCK_DELAY / 2;
+    for _ in 0..consts::MAX_LOCK_RESETS {
+        assert!(!engine.tick_lockdown(half));
+        assert!(engine.use_lock_reset());
+    }
+    // Without resets left, moves don't postpone the lockdown any more.
+    assert!(!engine.tick_lockdown(half));
+    assert!(!engine.use_lock_reset());
+    assert!(!engine.tick_lockdown(consts::LOCK_DELAY - half - Duration::from_millis(1)));
+    assert!(engine.tick_lockdown(Duration::from_millis(1)));
+    assert_eq!(engine.lockdown, None);
+}
+
+#[test]
+fn kick_to_new_lowest_row_gives_resets_back() {
+    let mut engine = engine_with(FigureType::T, R0);
+    // Rotation kicked 2 rows down by the last test.
+    let mut rotated = engine.clone();
+    assert!(rotated.rotate(true));
+    let target = rotated.piece.shifted(-1, -2);
+    let mut free = as_set(&engine.piece.tiles);
+    free.extend(target);
+    fill_except(&mut engine.board, &free);
+    engine.lock_resets.lowest_row = engine.piece.bottom();
+    engine.start_lockdown();
+    while engine.use_lock_reset() {}
+    assert!(engine.lock_resets_exhausted());
+
+    assert!(engine.rotate(true));
+    assert_eq!(engine.last_move.kick, 4);
+    assert_eq!(as_set(&engine.piece.tiles), as_set(&target));
+    assert!(!engine.lock_resets_exhausted());
+    assert_eq!(engine.lock_resets.count, 0);
+}
+
+#[test]
+fn same_seed_gives_same_figures() {
+    let mut first = TetrisEngine::with_seed(42);
+    let mut second = TetrisEngine::with_seed(42);
+    for _ in 0..30 {
+        assert_eq!(first.piece.figure, second.piece.figure);
+        assert!(first.bag.peek(3).eq(second.bag.peek(3)));
+        first.spawn_next();
+        second.spawn_next();
+    }
+}
+
+#[test]
+fn replay_roundtrip() {
+    let mut replay = Replay::new(7, GameMode::Sprint, BoardSize::NARROW, 2);
+    replay.push(3, 0, ReplayInput::Move { right: false });
+    replay.push(3, 1, ReplayInput::Rotate { clockwise: true });
+    replay.push(10, 0, ReplayInput::SoftDrop { active: true });
+    replay.push(12, 0, ReplayInput::SoftDrop { active: false });
+    replay.push(20, 1, ReplayInput::Hold);
+    replay.push(25, 0, ReplayInput::HardDrop);
+
+    let text = replay.to_string();
+    assert!(text.starts_with("seed 7\nmode sprint\nboard 6 40 20\nplayers 2\n3 0 left\n3 1 cw\n"));
+    assert_eq!(text.parse::<Replay>(), Ok(replay.clone()));
+
+    let at_three = replay
+        .inputs_at(3)
+        .map(|(player, input)| (player, *input))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        at_three,
+        [
+            (0, ReplayInput::Move { right: false }),
+            (1, ReplayInput::Rotate { clockwise: true })
+        ]
+    );
+    assert_eq!(replay.inputs_at(4).count(), 0);
+
+    let tide = "seed 1\nmode rising-tide\nboard 10 20 20\nplayers 1".parse::<Replay>();
+    assert_eq!(tide.map(|replay| replay.mode), Ok(GameMode::RisingTide));
+    assert!("3 0 left".parse::<Replay>().is_err());
+    assert!("seed 1\n5 0 left".parse::<Replay>().is_err());
+    assert!("seed 1\nmode ultra\nboard 10 20\n"
+        .parse::<Replay>()
+        .is_err());
+    let header = "seed 1\nmode ultra\nboard 10 20 20\nplayers 1\n";
+    assert!(format!("{header}5 0 left\n4 0 right")
+        .parse::<Replay>()
+        .is_err());
+    // Input of a player, who isn't in the game.
+    assert!(format!("{header}5 1 left").parse::<Replay>().is_err());
+}
+
+#[test]
+fn mode_goals() {
+    let mut engine = TetrisEngine::with_options(1, GameMode::Sprint, BoardSize::default());
+    engine.score.add_lines(consts::SPRINT_LINES - 1);
+    assert!(!engine.goal_reached());
+    engine.score.add_lines(1);
+    assert!(engine.goal_reached());
+
+    let mut engine = TetrisEngine::with_options(1, GameMode::Ultra, BoardSize::default());
+    engine.advance_clock(consts::ULTRA_TIME_LIMIT - Duration::from_millis(10));
+    assert!(!engine.goal_reached());
+    assert_eq!(
+        format_time(engine.mode.time_left(engine.elapsed).unwrap()),
+        "0:00.01"
+    );
+    engine.advance_clock(Duration::from_millis(10));
+    assert!(engine.goal_reached());
+
+    let mut engine = TetrisEngine::with_options(1, GameMode::RisingTide, BoardSize::default());
+    assert_eq!(engine.garbage_left, consts::DIG_GARBAGE_ROWS);
+    engine.garbage_left = 0;
+    assert!(engine.goal_reached());
+
+    let mut engine = TetrisEngine::with_options(1, GameMode::Marathon, BoardSize::default());
+    engine.score.add_lines(1000);
+    engine.advance_clock(Duration::from_secs(1000));
+    assert!(!engine.goal_reached());
+    assert_eq!(format_time(Duration::from_millis(83_456)), "1:23.45");
+}
+
+#[test]
+fn garbage_rows_have_single_hole() {
+    let mut engine = TetrisEngine::with_options(3, GameMode::Dig, BoardSize::default());
+    assert_eq!(engine.garbage_left, consts::DIG_GARBAGE_ROWS);
+    for y in 0..consts::DIG_GARBAGE_ROWS {
+        let holes = engine.board[y].iter().filter(|x| !x.is_occupied()).count();
+        assert_eq!(holes, 1);
+    }
+    assert!(engine.board[consts::DIG_GARBAGE_ROWS]
+        .iter()
+        .all(|x| !x.is_occupied()));
+    assert!(!engine.goal_reached());
+
+    // Fill the hole of the bottom garbage row.
+    let hole = engine.board[0]
+        .iter()
+        .position(|x| !x.is_occupied())
+        .unwrap();
+    engine.board.occupy((hole as i32, 0), Cell::Garbage);
+    engine.clear_lines(None);
+    assert_eq!(engine.garbage_left, consts::DIG_GARBAGE_ROWS - 1);
+}
+
+#[test]
+fn garbage_pushes_figure_up() {
+    let mut engine = TetrisEngine::new();
+    engine.piece = Piece::at(FigureType::O, 4, 0);
+    assert!(engine.add_garbage(2));
+    assert!(engine.fits(&engine.piece.tiles));
+    assert!(engine.piece.tiles.iter().all(|(_, y)| *y >= 2));
+
+    // Locked tiles pushed out of the board top out the player.
+    engine
+        .board
+        .occupy((0, engine.board.len() as i32 - 1), Cell::Garbage);
+    assert!(!engine.add_garbage(1));
+}
+
+#[test]
+fn figures_spawn_inside_every_board_size() {
+    assert_eq!(BoardSize::CLASSIC.spawn_position(), (4, 18));
+    assert_eq!(BoardSize::GUIDELINE.spawn_position(), (4, 19));
+    for size in BoardSize::presets() {
+        let mut engine = TetrisEngine::with_options(1, GameMode::Marathon, size);
+        assert_eq!(engine.board.len(), size.height);
+        assert!(engine.board.iter().all(|row| row.len() == size.width));
+        for &figure in FigureType::all() {
+            assert!(engine.spawn(figure), "{figure:?} on {size}");
+            assert!(engine.rotate(true), "{figure:?} on {size}");
+        }
+    }
+}
+
+#[test]
+fn garbage_sent_for_clears() {
+    let result = |cleared, t_spin, back_to_back| ClearResult {
+        cleared,
+        t_spin,
+        back_to_back,
+        combo: 0,
+        points: 0,
+    };
+    assert_eq!(result(0, Some(TSpin::Full), false).garbage(), 0);
+    assert_eq!(result(1, None, false).garbage(), 0);
+    assert_eq!(result(2, None, false).garbage(), 1);
+    assert_eq!(result(3, None, false).garbage(), 2);
+    assert_eq!(result(4, None, false).garbage(), 4);
+    assert_eq!(result(4, None, true).garbage(), 5);
+    assert_eq!(result(2, Some(TSpin::Full), false).garbage(), 4);
+}
+
+#[test]
+fn bot_prefers_clearing_lines() {
+    let mut engine = TetrisEngine::with_seed(3);
+    // Bottom row is filled except for the rightmost column.
+    for x in 0..engine.size.width as i32 - 1 {
+        engine.board.occupy((x, 0), Cell::Garbage);
+    }
+    engine.hold.locked = true;
+    engine.spawn(FigureType::I);
+    let plan = engine.plan_placement(&BotWeights::default()).unwrap();
+    assert_eq!(plan.inputs.last(), Some(&ReplayInput::HardDrop));
+    for input in &plan.inputs {
+        apply_input(&mut engine, input);
+    }
+    let t_spin = engine.lock();
+    assert_eq!(engine.clear_lines(t_spin).cleared, 1);
+}
+
+/// Soak test of the rules: the bot plays a long game
+/// and should never top out on an empty board.
+#[test]
+fn bot_survives_long_game() {
+    let mut engine = TetrisEngine::with_seed(11);
+    let weights = BotWeights::default();
+    for _ in 0..300 {
+        let plan = engine.plan_placement(&weights).unwrap();
+        for input in &plan.inputs {
+            apply_input(&mut engine, input);
+        }
+        let t_spin = engine.lock();
+        engine.clear_lines(t_spin);
+        assert!(engine.board.full_rows().is_empty());
+        assert!(engine.spawn_next(), "topped out at {:?}", engine.score);
+    }
+    assert!(engine.score.lines_cleared >= 100);
+}
+
+fn apply_input(engine: &mut TetrisEngine, input: &ReplayInput) {
+    match *input {
+        ReplayInput::Move { right } => assert!(engine.move_horizontally(right)),
+        ReplayInput::Rotate { clockwise } => assert!(engine.rotate(clockwise)),
+        ReplayInput::HardDrop => {
+            engine.hard_drop();
+        }
+        ReplayInput::Hold => assert_eq!(engine.hold(), Some(true)),
+        ReplayInput::SoftDrop { .. } => {}
+    }
+}
+
+#[test]
+fn locked_cells_remember_their_figure() {
+    let mut engine = TetrisEngine::with_seed(5);
+    engine.spawn(FigureType::T);
+    engine.hard_drop();
+    let tiles = engine.piece.tiles;
+    engine.lock();
+    for pos in tiles {
+        assert_eq!(engine.board.cell(pos), Cell::Figure(FigureType::T));
+    }
+    assert!(engine.spawn_next());
+    assert!(engine.add_garbage(1));
+    assert_eq!(
+        engine.board.cell((tiles[0].0, tiles[0].1 + 1)),
+        Cell::Figure(FigureType::T)
+    );
+    let garbage = (0..engine.size.width as i32)
+        .filter(|x| engine.board.cell((*x, 0)) == Cell::Garbage)
+        .count();
+    assert_eq!(garbage, engine.size.width - 1);
+    assert_eq!(engine.board.cell((-1, 0)), Cell::Empty);
+}
+
+#[test]
+fn garbage_between_lock_and_spawn_does_not_top_out() {
+    // The same order as in versus: lock, clear, garbage, spawn.
+    let mut engine = TetrisEngine::with_seed(5);
+    engine.spawn(FigureType::O);
+    engine.hard_drop();
+    let tiles = engine.piece.tiles;
+    let t_spin = engine.lock();
+    engine.clear_lines(t_spin);
+    assert!(engine.push_garbage_rows(1));
+    assert!(engine.spawn_next());
+    for (x, y) in tiles {
+        assert_eq!(engine.board.cell((x, y + 1)), Cell::Figure(FigureType::O));
+    }
+}
+
+#[test]
+fn held_figure_swapped_into_locked_tiles_blocks_out() {
+    let mut engine = TetrisEngine::with_seed(5);
+    let (_, spawn_y) = engine.size.spawn_position();
+    for y in spawn_y..spawn_y + 2 {
+        for x in 0..engine.size.width as i32 {
+            engine.board.occupy((x, y), Cell::Garbage);
+        }
+    }
+    assert_eq!(engine.hold(), Some(false));
+    assert!(!engine.fits(&engine.piece.tiles));
+}
@@ -1,175 +1,202 @@
+use std::{collections::VecDeque, time::Duration};
+
 use bevy::prelude::*;
 
+use crate::{
+    consts,
+    engine::{ReplayInput, Score, TSpin, TetrisEngine},
+    state::{Game, Player},
+};
+
+use super::timers::{CalloutTimer, GravityTimer, LineClearTimer, RisingTideTimer};
+
 #[derive(Debug, Copy, Clone, Component)]
 pub struct OnGameScreen;
 
-#[derive(Debug, Copy, Clone, Component)]
-pub enum Rotation {
-    // 0 - spawned state;
-    R0,
-    // R - 90 degrees clockwise from spawned;
-    RR,
-    // 2- 180 degrees any direction;
-    R2,
-    // L - 90 degrees counterclockwise from spawned;
-    RL,
+#[derive(Component)]
+pub struct ScoreLabel;
+
+#[derive(Component)]
+pub struct CalloutLabel;
+
+/// Cell of a small preview grid in the side panel.
+/// Index is the position of the figure in the upcoming queue.
+#[derive(Debug, Clone, Component)]
+pub struct NextFigureCell {
+    pub index: usize,
 }
 
-#[derive(Debug, Clone, Copy, Component)]
-pub enum FigureType {
-    O,
-    I,
-    S,
-    Z,
-    L,
-    J,
-    T,
+/// Cell of a small grid in the side panel,
+/// which shows currently held figure.
+#[derive(Debug, Clone, Component)]
+pub struct HoldFigureCell;
+
+#[derive(Debug, Clone, Component)]
+pub struct GridCell;
+
+#[derive(Debug, Clone, Component, PartialEq, Eq, Hash)]
+pub struct GridPosition {
+    pub x: i32,
+    pub y: i32,
 }
 
-impl rand::distributions::Distribution<FigureType> for rand::distributions::Standard {
-    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> FigureType {
-        match rng.gen_range(0..=6) {
-            0 => FigureType::O,
-            1 => FigureType::I,
-            2 => FigureType::S,
-            3 => FigureType::Z,
-            4 => FigureType::L,
-            5 => FigureType::J,
-            6 => FigureType::T,
-            _ => unreachable!(),
-        }
-    }
+/// Player entity, whose game is shown by the UI node.
+#[derive(Debug, Clone, Copy, Component, Deref)]
+pub struct Owner(pub Entity);
+
+/// Key bindings of a player.
+#[derive(Debug, Clone, Component)]
+pub struct Controls {
+    pub left: &'static [KeyCode],
+    pub right: &'static [KeyCode],
+    pub soft_drop: &'static [KeyCode],
+    pub hard_drop: &'static [KeyCode],
+    pub rotate_cw: &'static [KeyCode],
+    pub rotate_ccw: &'static [KeyCode],
+    pub hold: &'static [KeyCode],
 }
 
-impl FigureType {
-    /// Get all possible figure types.
-    /// Used to implement random generation of
-    /// the next figure.
-    ///
-    /// Checkout TetroBag.
-    pub const fn all() -> &'static [FigureType] {
-        &[
-            FigureType::O,
-            FigureType::I,
-            FigureType::S,
-            FigureType::Z,
-            FigureType::L,
-            FigureType::J,
-            FigureType::T,
-        ]
-    }
-    /// This function returns the dots that represent the figure in its spawned state.
-    /// This function should be used only when spawning figures.
-    ///
-    /// Every point is shown as (x, y);
-    pub const fn to_dots(&self) -> &'static [(i32, i32)] {
-        match self {
-            FigureType::O => &[(0, 0), (1, 0), (0, 1), (1, 1)],
-            FigureType::I => &[(0, 0), (1, 0), (2, 0), (3, 0)],
-            FigureType::S => &[(0, 0), (1, 0), (1, 1), (2, 1)],
-            FigureType::Z => &[(1, 0), (2, 0), (0, 1), (1, 1)],
-            FigureType::L => &[(2, 1), (0, 0), (1, 0), (2, 0)],
-            FigureType::J => &[(0, 1), (0, 0), (1, 0), (2, 0)],
-            FigureType::T => &[(0, 0), (1, 0), (2, 0), (1, 1)],
-        }
-    }
-    // Finds the center point of the figure in its current rotation.
-    // This is used to calculate the offset of the figure when rotating it.
-    // Every point is shown as (x, y);
-    pub const fn center_point(&self, rot: &Rotation) -> (i32, i32) {
-        match self {
-            FigureType::O => match rot {
-                Rotation::R0 => (0, 0),
-                Rotation::RR => (0, 1),
-                Rotation::R2 => (1, 1),
-                Rotation::RL => (1, 0),
-            },
-            FigureType::L | FigureType::J | FigureType::Z | FigureType::S | FigureType::T => {
-                match rot {
-                    Rotation::R0 => (1, 0),
-                    Rotation::RR => (0, 1),
-                    Rotation::R2 => (1, 1),
-                    Rotation::RL => (1, 1),
-                }
-            }
-            FigureType::I => match rot {
-                Rotation::R0 => (1, 0),
-                Rotation::RR => (0, 2),
-                Rotation::R2 => (2, 0),
-                Rotation::RL => (0, 1),
-            },
-        }
-    }
+impl Controls {
+    /// Controls of a single player game.
+    pub const SOLO: Controls = Controls {
+        left: &[KeyCode::ArrowLeft, KeyCode::KeyH],
+        right: &[KeyCode::ArrowRight, KeyCode::KeyL],
+        soft_drop: &[KeyCode::ArrowDown, KeyCode::KeyJ],
+        hard_drop: &[KeyCode::Space],
+        rotate_cw: &[KeyCode::ArrowUp, KeyCode::KeyK],
+        rotate_ccw: &[KeyCode::KeyZ],
+        hold: &[KeyCode::KeyC, KeyCode::ShiftLeft, KeyCode::ShiftRight],
+    };
+    /// Left player of a versus game.
+    pub const WASD: Controls = Controls {
+        left: &[KeyCode::KeyA],
+        right: &[KeyCode::KeyD],
+        soft_drop: &[KeyCode::KeyS],
+        hard_drop: &[KeyCode::Space],
+        rotate_cw: &[KeyCode::KeyW],
+        rotate_ccw: &[KeyCode::KeyZ],
+        hold: &[KeyCode::KeyC, KeyCode::ShiftLeft],
+    };
+    /// Right player of a versus game.
+    pub const ARROWS: Controls = Controls {
+        left: &[KeyCode::ArrowLeft],
+        right: &[KeyCode::ArrowRight],
+        soft_drop: &[KeyCode::ArrowDown],
+        hard_drop: &[KeyCode::Enter],
+        rotate_cw: &[KeyCode::ArrowUp],
+        rotate_ccw: &[KeyCode::ControlRight],
+        hold: &[KeyCode::ShiftRight],
+    };
 
-    pub const fn tests_for_single_rot(&self, rot: &Rotation) -> &'static [(i32, i32)] {
-        match self {
-            FigureType::L | FigureType::J | FigureType::Z | FigureType::S | FigureType::T => {
-                match rot {
-                    Rotation::R0 => &[(0, 0), (0, 0), (0, 0), (0, 0), (0, 0)],
-                    Rotation::RR => &[(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
-                    Rotation::R2 => &[(0, 0), (0, 0), (0, 0), (0, 0), (0, 0)],
-                    Rotation::RL => &[(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
-                }
-            }
-            FigureType::O => match rot {
-                Rotation::R0 => &[(0, 0)],
-                Rotation::RR => &[(0, -1)],
-                Rotation::R2 => &[(-1, -1)],
-                Rotation::RL => &[(-1, 0)],
-            },
-            FigureType::I => match rot {
-                Rotation::R0 => &[(0, 0), (-1, 0), (2, 0), (-1, 0), (2, 0)],
-                Rotation::RR => &[(-1, 0), (0, 0), (0, 0), (0, 1), (0, -2)],
-                Rotation::R2 => &[(-1, 1), (1, 1), (-2, 1), (1, 0), (-2, 0)],
-                Rotation::RL => &[(0, 1), (0, 1), (0, 1), (0, -1), (0, 2)],
-            },
+    pub fn for_player(index: usize, players: usize) -> Controls {
+        match (index, players) {
+            (_, 1) => Controls::SOLO,
+            (0, _) => Controls::WASD,
+            _ => Controls::ARROWS,
         }
     }
+}
 
-    /// This function calculates test offsets,
-    /// based on the current and next rotations.
-    /// This is generally made to perform wall-kicks and fit tetronomio
-    /// in places where they would normally not fit.
-    pub fn tests_for_rot(&self, prev: &Rotation, next: &Rotation) -> Vec<(i32, i32)> {
-        self.tests_for_single_rot(prev)
-            .iter()
-            .zip(self.tests_for_single_rot(next))
-            .map(|(prev, next)| (prev.0 - next.0, prev.1 - next.1))
-            .collect()
-    }
+/// Player controlled by the bot instead of the keyboard.
+///
+/// Inputs of the planned placement are sent one at a time,
+/// the next placement is planned after the figure is placed.
+#[derive(Debug, Clone, Component)]
+pub struct Autoplayer {
+    pub inputs: VecDeque<ReplayInput>,
+    // Set after the hard drop, until the figure is placed.
+    pub dropped: bool,
+    pub timer: Timer,
 }
 
-impl Rotation {
-    pub fn left(&self) -> Self {
-        match self {
-            Rotation::R0 => Rotation::RL,
-            Rotation::RL => Rotation::R2,
-            Rotation::R2 => Rotation::RR,
-            Rotation::RR => Rotation::R0,
+impl Default for Autoplayer {
+    fn default() -> Self {
+        Self {
+            inputs: VecDeque::new(),
+            dropped: false,
+            timer: Timer::new(consts::BOT_INPUT_INTERVAL, TimerMode::Repeating),
         }
     }
+}
 
-    pub fn right(&self) -> Self {
-        match self {
-            Rotation::R0 => Rotation::RR,
-            Rotation::RR => Rotation::R2,
-            Rotation::R2 => Rotation::RL,
-            Rotation::RL => Rotation::R0,
-        }
-    }
+// Indicates wether soft drop is active or not.
+#[derive(Debug, Clone, Default, Component)]
+pub struct SoftDrop {
+    pub active: bool,
 }
 
-#[derive(Component)]
-pub struct ScoreLabel;
+/// State of the held horizontal move.
+#[derive(Debug, Clone, Default, Component)]
+pub struct AutoShift {
+    // Direction of the held key, the last pressed one wins.
+    pub right: Option<bool>,
+    pub delay: Timer,
+    pub repeat: Timer,
+    // Moves sent since the game tick, right ones are positive.
+    // The game applies them on the next tick.
+    pub pending: i32,
+    pub tick: u64,
+}
 
-#[derive(Debug, Clone, Component)]
-pub struct GridCell;
+/// Placed figure, which is waiting for its filled rows to collapse.
+///
+/// Gravity, controls and spawning are suspended
+/// while the line clear animation is playing.
+#[derive(Debug, Clone, Default, Component)]
+pub struct LineClear {
+    pub pending: bool,
+    pub rows: Vec<usize>,
+    pub t_spin: Option<TSpin>,
+}
 
-#[derive(Debug, Clone, Component, PartialEq, Eq, Hash)]
-pub struct GridPosition {
-    pub x: i32,
-    pub y: i32,
+/// Garbage rows received from the opponent,
+/// which rise before the next figure is spawned.
+#[derive(Debug, Clone, Default, Component, Deref, DerefMut)]
+pub struct PendingGarbage(pub usize);
+
+/// Game of a single player with everything needed to run it.
+#[derive(Bundle)]
+pub struct PlayerBundle {
+    pub player: Player,
+    pub game: Game,
+    pub controls: Controls,
+    pub gravity_timer: GravityTimer,
+    pub line_clear_timer: LineClearTimer,
+    pub rising_tide_timer: RisingTideTimer,
+    pub callout_timer: CalloutTimer,
+    pub soft_drop: SoftDrop,
+    pub auto_shift: AutoShift,
+    pub line_clear: LineClear,
+    pub pending_garbage: PendingGarbage,
 }
 
-#[derive(Debug, Clone, Component)]
-pub struct PlayableTile;
+impl PlayerBundle {
+    pub fn new(player: Player, game: TetrisEngine, controls: Controls) -> Self {
+        // Timers should be in the same state at the start of every game,
+        // otherwise replays won't be reproduced.
+        let mut callout_timer = Timer::new(Duration::from_millis(1500), TimerMode::Once);
+        callout_timer.tick(Duration::from_secs(10));
+        Self {
+            player,
+            game: Game(game),
+            controls,
+            gravity_timer: GravityTimer(Timer::new(
+                Score::default().gravity_interval(),
+                TimerMode::Repeating,
+            )),
+            line_clear_timer: LineClearTimer(Timer::new(
+                consts::LINE_CLEAR_DURATION,
+                TimerMode::Once,
+            )),
+            rising_tide_timer: RisingTideTimer(Timer::new(
+                consts::RISING_TIDE_INTERVAL,
+                TimerMode::Repeating,
+            )),
+            callout_timer: CalloutTimer(callout_timer),
+            soft_drop: SoftDrop::default(),
+            auto_shift: AutoShift::default(),
+            line_clear: LineClear::default(),
+            pending_garbage: PendingGarbage::default(),
+        }
+    }
+}
@@ -1,20 +1,144 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 
+use crate::engine::{ReplayInput, TSpin, TopOut};
+
+// Every event is addressed to the player entity it belongs to.
+
+/// Figure was locked on the board.
 #[derive(Debug, Clone, Event)]
-pub struct FigurePlaced;
+pub struct FigurePlaced {
+    pub player: Entity,
+    pub t_spin: Option<TSpin>,
+}
 
+/// Player has topped out, which ends the game for everyone.
 #[derive(Debug, Clone, Event)]
-pub struct GameOver;
+pub struct GameOver {
+    pub player: Entity,
+    pub reason: TopOut,
+}
 
 #[derive(Debug, Clone, Event)]
 pub struct MoveTetronomioHorizontally {
+    pub player: Entity,
     pub right: bool,
 }
 
 #[derive(Debug, Clone, Event)]
 pub struct RotateTetronomio {
+    pub player: Entity,
     pub clockwise: bool,
 }
 
 #[derive(Debug, Clone, Event)]
-pub struct HardDrop;
+pub struct HardDrop {
+    pub player: Entity,
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct HoldFigure {
+    pub player: Entity,
+}
+
+/// Soft drop key was pressed or released.
+#[derive(Debug, Clone, Event)]
+pub struct SoftDropToggled {
+    pub player: Entity,
+    pub active: bool,
+}
+
+/// Short message about special clears,
+/// shown in the side panel.
+#[derive(Debug, Clone, Event)]
+pub struct Callout {
+    pub player: Entity,
+    pub text: String,
+}
+
+/// Garbage rows sent by the player to the opponents.
+#[derive(Debug, Clone, Event)]
+pub struct SendGarbage {
+    pub player: Entity,
+    pub rows: usize,
+}
+
+/// Writers of the control events, which are sent
+/// either by player inputs or by a played back replay.
+#[derive(SystemParam)]
+pub struct ControlWriters<'w> {
+    moves: EventWriter<'w, MoveTetronomioHorizontally>,
+    rotations: EventWriter<'w, RotateTetronomio>,
+    hard_drops: EventWriter<'w, HardDrop>,
+    holds: EventWriter<'w, HoldFigure>,
+    soft_drops: EventWriter<'w, SoftDropToggled>,
+}
+
+impl ControlWriters<'_> {
+    pub fn send(&mut self, player: Entity, input: ReplayInput) {
+        match input {
+            ReplayInput::Move { right } => {
+                self.moves
+                    .send(MoveTetronomioHorizontally { player, right });
+            }
+            ReplayInput::Rotate { clockwise } => {
+                self.rotations.send(RotateTetronomio { player, clockwise });
+            }
+            ReplayInput::HardDrop => {
+                self.hard_drops.send(HardDrop { player });
+            }
+            ReplayInput::Hold => {
+                self.holds.send(HoldFigure { player });
+            }
+            ReplayInput::SoftDrop { active } => {
+                self.soft_drops.send(SoftDropToggled { player, active });
+            }
+        }
+    }
+}
+
+/// Readers of the control events, used to record them.
+#[derive(SystemParam)]
+pub struct ControlReaders<'w, 's> {
+    moves: EventReader<'w, 's, MoveTetronomioHorizontally>,
+    rotations: EventReader<'w, 's, RotateTetronomio>,
+    hard_drops: EventReader<'w, 's, HardDrop>,
+    holds: EventReader<'w, 's, HoldFigure>,
+    soft_drops: EventReader<'w, 's, SoftDropToggled>,
+}
+
+impl ControlReaders<'_, '_> {
+    /// All new control events in the order
+    /// they are applied by the control systems.
+    pub fn read(&mut self) -> Vec<(Entity, ReplayInput)> {
+        let soft_drops = self.soft_drops.read().map(|event| {
+            let input = ReplayInput::SoftDrop {
+                active: event.active,
+            };
+            (event.player, input)
+        });
+        let moves = self.moves.read().map(|event| {
+            let input = ReplayInput::Move { right: event.right };
+            (event.player, input)
+        });
+        let rotations = self.rotations.read().map(|event| {
+            let input = ReplayInput::Rotate {
+                clockwise: event.clockwise,
+            };
+            (event.player, input)
+        });
+        let holds = self
+            .holds
+            .read()
+            .map(|event| (event.player, ReplayInput::Hold));
+        let hard_drops = self
+            .hard_drops
+            .read()
+            .map(|event| (event.player, ReplayInput::HardDrop));
+        soft_drops
+            .chain(moves)
+            .chain(rotations)
+            .chain(holds)
+            .chain(hard_drops)
+            .collect()
+    }
+}
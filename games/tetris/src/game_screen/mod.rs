@@ -1,79 +1,177 @@
-use std::time::Duration;
-
 use bevy::prelude::*;
+use cgc_lib::simple_menu::{SimpleMenuButton, SimpleMenuPlugin};
 
-use crate::{state::GameState, utils::systems::despawn_screen};
+use crate::{
+    consts,
+    state::{Demo, GameState, PauseState, Playback, Player, ToppedOut},
+    utils::systems::despawn_screen,
+};
 
 pub struct GameScreenPlugin;
 
 mod components;
 mod debug;
 mod events;
+mod pause;
 mod resources;
 mod systems;
 mod timers;
 
 impl Plugin for GameScreenPlugin {
     fn build(&self, app: &mut App) {
+        let asset_server = app.world().resource::<AssetServer>();
+        let text_style = TextStyle {
+            font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
+            font_size: 16.,
+            color: consts::FOREGROUND_COLOR,
+        };
+        let button = |text: &str, event| SimpleMenuButton {
+            text: String::from(text),
+            event,
+            text_style: text_style.clone(),
+        };
+        let buttons = vec![
+            button("RESUME", pause::PauseAction::Resume),
+            button("RESTART", pause::PauseAction::Restart),
+            button("QUIT TO MENU", pause::PauseAction::Quit),
+        ];
+        let title_style = TextStyle {
+            font_size: 32.,
+            ..text_style.clone()
+        };
+
         app.add_systems(
             OnEnter(GameState::Playing),
+            (despawn_screen::<Player>, systems::reset_game).chain(),
+        )
+        // Restarting from the pause menu doesn't leave the playing state,
+        // so the game is reset the same way here.
+        .add_systems(
+            Update,
             (
-                systems::setup_layout,
+                despawn_screen::<components::OnGameScreen>,
+                despawn_screen::<Player>,
                 systems::reset_game,
-                systems::spawn_figure,
+            )
+                .chain()
+                .run_if(pause::restart_requested),
+        )
+        .add_event::<pause::PauseAction>()
+        .add_plugins(SimpleMenuPlugin {
+            on_state: PauseState::Paused,
+            btn_color: consts::BACKGROUND_COLOR,
+            border_color: consts::FOREGROUND_COLOR,
+            content_row_gap: Val::Percent(15.),
+            buttons_row_gap: Val::Percent(20.),
+            buttons,
+            extra_children: Some(move |parent: &mut ChildBuilder| {
+                parent.spawn(TextBundle {
+                    text: Text::from_section("PAUSED", title_style.clone()),
+                    ..default()
+                });
+            }),
+        })
+        .add_systems(OnEnter(PauseState::Paused), pause::hide_game_screen)
+        .add_systems(OnExit(PauseState::Paused), pause::show_game_screen)
+        .add_systems(
+            Update,
+            (
+                pause::toggle_pause
+                    .run_if(in_state(GameState::Playing).and_then(not(demo_is_playing))),
+                pause::on_pause_action.run_if(on_event::<pause::PauseAction>()),
             ),
         )
-        .init_resource::<resources::TetroBag>()
-        .insert_resource(resources::GameBoard::new())
-        .insert_resource(timers::GravityTimer(Timer::new(
-            Duration::from_secs_f64(0.4),
-            TimerMode::Repeating,
-        )))
-        .insert_resource(timers::LockdownTimer(Timer::new(
-            Duration::from_millis(500),
-            TimerMode::Once,
-        )))
-        .insert_resource(resources::SoftDrop::default())
         .add_event::<events::FigurePlaced>()
         .add_event::<events::GameOver>()
         .add_event::<events::MoveTetronomioHorizontally>()
         .add_event::<events::RotateTetronomio>()
         .add_event::<events::HardDrop>()
+        .add_event::<events::HoldFigure>()
+        .add_event::<events::Callout>()
+        .add_event::<events::SoftDropToggled>()
+        .add_event::<events::SendGarbage>()
+        .insert_resource(resources::GameTick::default())
+        .insert_resource(resources::Recording::default())
+        .insert_resource(timers::TopOutTimer(Timer::new(
+            consts::TOP_OUT_DURATION,
+            TimerMode::Once,
+        )))
         .add_systems(
             Update,
             (
-                // Logical systems
+                // Rendering systems
                 systems::color_grid,
                 systems::update_score_label,
-                systems::handle_inputs,
-                systems::gravity,
-                systems::lockdown_tiles,
-                systems::clear_lines,
-                // Control systemset. It runs after handle_inputs
-                // to reduce latency between input and action.
-                (
-                    systems::move_tetronomio_horizontally
-                        .run_if(on_event::<events::MoveTetronomioHorizontally>()),
-                    systems::rotate_tetronomio.run_if(on_event::<events::RotateTetronomio>()),
-                    systems::hard_drop
-                        .run_if(on_event::<events::HardDrop>())
-                        .before(systems::gravity),
-                )
-                    .after(systems::handle_inputs),
+                systems::update_next_figures,
+                systems::update_hold_figure,
+                systems::handle_inputs.run_if(not(replay_is_playing)),
+                systems::auto_shift.run_if(not(replay_is_playing)),
+                systems::show_callout.run_if(on_event::<events::Callout>()),
+                systems::hide_callout,
+                systems::stop_demo.run_if(demo_is_playing),
+                systems::finish_top_out.run_if(topped_out),
                 // Debug systemset.
                 (
                     debug::center_point.after(systems::color_grid),
                     debug::stop_gravity,
                 ),
             )
-                .distributive_run_if(in_state(GameState::Playing)),
+                .distributive_run_if(in_state(PauseState::Running)),
+        )
+        // Game rules run with a fixed timestep, so the same inputs
+        // at the same ticks always give the same game.
+        // Timers are only ticked by these systems,
+        // so pausing them freezes the game.
+        // Every player is suspended on its own
+        // while its filled lines are being cleared.
+        .add_systems(
+            FixedUpdate,
+            (
+                systems::advance_tick,
+                systems::play_replay.run_if(replay_is_playing),
+                systems::autoplay,
+                systems::record_inputs.run_if(not(replay_is_playing)),
+                (
+                    systems::toggle_soft_drop.run_if(on_event::<events::SoftDropToggled>()),
+                    systems::move_tetronomio_horizontally
+                        .run_if(on_event::<events::MoveTetronomioHorizontally>()),
+                    systems::rotate_tetronomio.run_if(on_event::<events::RotateTetronomio>()),
+                    systems::hold_figure.run_if(on_event::<events::HoldFigure>()),
+                    systems::hard_drop.run_if(on_event::<events::HardDrop>()),
+                )
+                    .chain(),
+                systems::gravity,
+                systems::lockdown_tiles,
+                systems::rising_tide,
+                systems::start_line_clear.run_if(on_event::<events::FigurePlaced>()),
+                systems::collapse_lines,
+                systems::receive_garbage.run_if(on_event::<events::SendGarbage>()),
+                systems::check_goal,
+                systems::top_out.run_if(on_event::<events::GameOver>()),
+            )
+                .chain()
+                .run_if(in_state(PauseState::Running).and_then(not(topped_out))),
         )
         .add_systems(
             OnExit(GameState::Playing),
             (
                 despawn_screen::<components::OnGameScreen>,
-                despawn_screen::<components::PlayableTile>,
+                systems::finish_replay,
             ),
         );
     }
 }
+
+/// The game is over as soon as any player has topped out,
+/// only the top out animation is left.
+fn topped_out(players: Query<(), With<ToppedOut>>) -> bool {
+    !players.is_empty()
+}
+
+fn demo_is_playing(demo: Res<Demo>) -> bool {
+    **demo
+}
+
+fn replay_is_playing(playback: Res<Playback>) -> bool {
+    playback.is_some()
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+
+use crate::state::{GameState, PauseState};
+
+use super::components::OnGameScreen;
+
+/// Buttons of the pause menu.
+#[derive(Debug, Clone, Event)]
+pub enum PauseAction {
+    Resume,
+    Restart,
+    Quit,
+}
+
+pub fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    pause_state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    if !keys.any_just_pressed([KeyCode::Escape, KeyCode::KeyP]) {
+        return;
+    }
+    next_state.set(match pause_state.get() {
+        PauseState::Running => PauseState::Paused,
+        PauseState::Paused => PauseState::Running,
+    });
+}
+
+/// Hides the boards and the side panels while the game is paused,
+/// so the stack can't be studied with the time frozen.
+pub fn hide_game_screen(mut screen_query: Query<&mut Visibility, With<OnGameScreen>>) {
+    for mut visibility in &mut screen_query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+pub fn show_game_screen(mut screen_query: Query<&mut Visibility, With<OnGameScreen>>) {
+    for mut visibility in &mut screen_query {
+        *visibility = Visibility::Inherited;
+    }
+}
+
+/// Restarted game is reset by the game screen, see [`restart_requested`].
+pub fn on_pause_action(
+    mut event_reader: EventReader<PauseAction>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for event in event_reader.read() {
+        match event {
+            PauseAction::Resume | PauseAction::Restart => {
+                pause_state.set(PauseState::Running);
+            }
+            PauseAction::Quit => {
+                game_state.set(GameState::StartMenu);
+            }
+        }
+    }
+}
+
+pub fn restart_requested(mut event_reader: EventReader<PauseAction>) -> bool {
+    event_reader
+        .read()
+        .any(|event| matches!(event, PauseAction::Restart))
+}
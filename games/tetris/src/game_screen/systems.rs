@@ -1,5 +1,3 @@
-use std::time::Duration;
-
 use bevy::{
     prelude::*,
     utils::{HashMap, HashSet},
@@ -7,47 +5,225 @@ use bevy::{
 
 use crate::{
     consts,
-    state::{GameState, Score},
-    utils::rotations::{rotate_left, rotate_right},
+    engine::{
+        format_time, BoardSize, BotWeights, ClearResult, FigureType, GameMode, Replay, ReplayInput,
+        TSpin, TetrisEngine, TopOut,
+    },
+    state::{Demo, Game, GameSettings, GameState, Playback, Player, ToppedOut},
 };
 
 use super::{
     components::{
-        FigureType, GridCell, GridPosition, OnGameScreen, PlayableTile, Rotation, ScoreLabel,
+        AutoShift, Autoplayer, CalloutLabel, Controls, GridCell, GridPosition, HoldFigureCell,
+        LineClear, NextFigureCell, OnGameScreen, Owner, PendingGarbage, PlayerBundle, ScoreLabel,
+        SoftDrop,
     },
-    events,
-    resources::{GameBoard, SoftDrop, TetroBag},
-    timers::{self, LockdownTimer},
+    events::{self, ControlReaders, ControlWriters},
+    resources::{GameTick, Recording},
+    timers,
 };
 
+/// Starts a new game with the selected settings.
+///
+/// Every player gets an entity with its own game and timers.
+/// All players share the seed, so they get the same figures.
+/// Played back game uses the seed and the settings of the replay,
+/// otherwise a random seed is used. Demo game is played
+/// by the bot alone.
 pub fn reset_game(
-    mut score: ResMut<Score>,
-    mut board: ResMut<GameBoard>,
-    mut bag: ResMut<TetroBag>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut tick: ResMut<GameTick>,
+    mut recording: ResMut<Recording>,
+    playback: Res<Playback>,
+    settings: Res<GameSettings>,
+    demo: Res<Demo>,
 ) {
-    score.score = 0;
-    score.lines_cleared = 0;
-    board.reset();
-    bag.reset();
-}
-
-pub fn spawn_figure(mut commands: Commands, mut bag: ResMut<TetroBag>) {
-    let fig = bag.draw_next();
-    let fig_dots = fig.to_dots();
-    for (x, y) in fig_dots {
-        commands.spawn((
-            fig,
-            PlayableTile,
-            Rotation::R0,
-            GridPosition {
-                x: x + consts::START_X_POSITION,
-                y: y + consts::START_Y_POSITION,
-            },
-        ));
+    let (seed, mode, size, players) = match playback.0.as_ref() {
+        Some(replay) => (replay.seed, replay.mode, replay.size, replay.players),
+        None => (
+            rand::random(),
+            settings.mode,
+            settings.board,
+            settings.players,
+        ),
+    };
+    **recording = Replay::new(seed, mode, size, players);
+    tick.0 = 0;
+    let entities = (0..players)
+        .map(|index| {
+            let mut player = commands.spawn(PlayerBundle::new(
+                Player { index },
+                TetrisEngine::with_options(seed, mode, size),
+                Controls::for_player(index, players),
+            ));
+            if **demo {
+                player.insert(Autoplayer::default());
+            }
+            player.id()
+        })
+        .collect::<Vec<_>>();
+    setup_layout(&mut commands, &asset_server, &entities, &size);
+}
+
+pub fn advance_tick(mut tick: ResMut<GameTick>, mut players: Query<&mut Game>, time: Res<Time>) {
+    tick.0 += 1;
+    for mut game in &mut players {
+        game.advance_clock(time.delta());
+    }
+}
+
+/// Ends the game when one of the players reaches the goal of the mode.
+pub fn check_goal(players: Query<&Game>, mut state: ResMut<NextState<GameState>>) {
+    if players.iter().any(|game| game.goal_reached()) {
+        state.set(GameState::GameOver);
+    }
+}
+
+/// Marks players who have topped out and starts the top out animation.
+/// The game rules are stopped from now on.
+pub fn top_out(
+    mut commands: Commands,
+    mut game_over_events: EventReader<events::GameOver>,
+    mut timer: ResMut<timers::TopOutTimer>,
+) {
+    for event in game_over_events.read() {
+        commands.entity(event.player).insert(ToppedOut {
+            reason: event.reason,
+        });
+        timer.reset();
+    }
+}
+
+/// Shows the game over screen after the top out animation.
+pub fn finish_top_out(
+    mut timer: ResMut<timers::TopOutTimer>,
+    time: Res<Time>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if timer.tick(time.delta()).just_finished() {
+        state.set(GameState::GameOver);
+    }
+}
+
+/// Pushes up a new garbage row from time to time in the rising tide mode.
+pub fn rising_tide(
+    mut players: Query<(Entity, &mut Game, &mut timers::RisingTideTimer, &LineClear)>,
+    time: Res<Time>,
+    mut game_over_events: EventWriter<events::GameOver>,
+) {
+    for (player, mut game, mut timer, line_clear) in &mut players {
+        if game.mode != GameMode::RisingTide || line_clear.pending {
+            continue;
+        }
+        if timer.tick(time.delta()).just_finished() && !game.add_garbage(1) {
+            game_over_events.send(events::GameOver {
+                player,
+                reason: TopOut::PushOut,
+            });
+        }
+    }
+}
+
+/// Sends inputs of the replay recorded at the current tick.
+pub fn play_replay(
+    playback: Res<Playback>,
+    tick: Res<GameTick>,
+    players: Query<(Entity, &Player)>,
+    mut controls: ControlWriters,
+) {
+    let Some(replay) = playback.0.as_ref() else {
+        return;
+    };
+    for (index, input) in replay.inputs_at(tick.0) {
+        if let Some((entity, _)) = players.iter().find(|(_, player)| player.index == index) {
+            controls.send(entity, *input);
+        }
+    }
+}
+
+/// Plays for the players controlled by the bot.
+///
+/// A placement is planned for every new figure and its inputs
+/// are sent through the same events as inputs of a player.
+pub fn autoplay(
+    mut players: Query<(Entity, &Game, &mut Autoplayer, &LineClear)>,
+    time: Res<Time>,
+    mut controls: ControlWriters,
+) {
+    for (player, game, mut autoplayer, line_clear) in &mut players {
+        // Dropped figure is placed after the lockdown.
+        if line_clear.pending {
+            autoplayer.dropped = false;
+            continue;
+        }
+        if autoplayer.dropped || !autoplayer.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        if autoplayer.inputs.is_empty() {
+            autoplayer.inputs = game
+                .plan_placement(&BotWeights::default())
+                .map(|plan| plan.inputs.into())
+                .unwrap_or_else(|| [ReplayInput::HardDrop].into());
+        }
+        if let Some(input) = autoplayer.inputs.pop_front() {
+            autoplayer.dropped = input == ReplayInput::HardDrop;
+            controls.send(player, input);
+        }
     }
 }
 
-pub fn setup_layout(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// Returns to the start menu from the demo game on any key.
+pub fn stop_demo(key: Res<ButtonInput<KeyCode>>, mut state: ResMut<NextState<GameState>>) {
+    if key.get_just_pressed().next().is_some() {
+        state.set(GameState::StartMenu);
+    }
+}
+
+/// Stores inputs of the players along with the current tick.
+pub fn record_inputs(
+    tick: Res<GameTick>,
+    mut recording: ResMut<Recording>,
+    players: Query<&Player>,
+    mut controls: ControlReaders,
+) {
+    for (entity, input) in controls.read() {
+        if let Ok(player) = players.get(entity) {
+            recording.push(tick.0, player.index, input);
+        }
+    }
+}
+
+/// Saves the finished game, so it can be watched from the start menu.
+/// Played back, demo and abandoned games are not saved.
+pub fn finish_replay(
+    recording: Res<Recording>,
+    mut playback: ResMut<Playback>,
+    demo: Res<Demo>,
+    state: Res<State<GameState>>,
+) {
+    // The state is already the next one when the game screen is left,
+    // a game quit from the pause menu goes straight to the start menu.
+    let finished = *state.get() == GameState::GameOver;
+    if playback.take().is_some() || **demo || !finished {
+        return;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(err) = std::fs::write(consts::REPLAY_FILE, recording.to_string()) {
+        warn!("Failed to save the replay: {err}");
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = recording;
+}
+
+/// Builds the screen with a board and a side panel for every player.
+fn setup_layout(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    players: &[Entity],
+    size: &BoardSize,
+) {
+    let share = 100. / players.len() as f32;
     commands
         .spawn((
             NodeBundle {
@@ -55,7 +231,15 @@ pub fn setup_layout(mut commands: Commands, asset_server: Res<AssetServer>) {
                     display: Display::Grid,
                     width: Val::Percent(100.),
                     height: Val::Percent(100.),
-                    grid_template_columns: vec![GridTrack::percent(60.), GridTrack::percent(40.)],
+                    grid_template_columns: players
+                        .iter()
+                        .flat_map(|_| {
+                            [
+                                GridTrack::percent(share * 0.6),
+                                GridTrack::percent(share * 0.4),
+                            ]
+                        })
+                        .collect(),
                     ..default()
                 },
                 ..default()
@@ -63,71 +247,170 @@ pub fn setup_layout(mut commands: Commands, asset_server: Res<AssetServer>) {
             OnGameScreen,
         ))
         .with_children(|parent| {
-            parent
-                .spawn(NodeBundle {
-                    style: Style {
-                        align_self: AlignSelf::Center,
-                        justify_self: JustifySelf::Center,
-                        width: Val::Percent(100.),
-                        height: Val::Percent(100.),
-                        max_width: Val::Px(600.),
-                        aspect_ratio: Some(1.0),
-                        display: Display::Grid,
-                        align_items: AlignItems::Center,
-                        grid_template_columns: RepeatedGridTrack::percent(10, 10.),
-                        grid_template_rows: RepeatedGridTrack::percent(20, 5.),
-                        grid_auto_flow: GridAutoFlow::Column,
+            for &player in players {
+                let owner = Owner(player);
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_self: AlignSelf::Center,
+                            justify_self: JustifySelf::Center,
+                            width: Val::Percent(100.),
+                            height: Val::Percent(100.),
+                            max_width: Val::Px(600.),
+                            aspect_ratio: Some(1.0),
+                            display: Display::Grid,
+                            align_items: AlignItems::Center,
+                            grid_template_columns: RepeatedGridTrack::percent(
+                                size.width as u16,
+                                100. / size.width as f32,
+                            ),
+                            grid_template_rows: RepeatedGridTrack::percent(
+                                size.visible as u16,
+                                100. / size.visible as f32,
+                            ),
+                            grid_auto_flow: GridAutoFlow::Column,
+                            ..default()
+                        },
                         ..default()
-                    },
-                    ..default()
-                })
-                .with_children(setup_main_grid_cells);
-            // Node for score and upcoming figures
-            parent
-                .spawn(NodeBundle {
-                    style: Style {
-                        width: Val::Percent(100.),
-                        height: Val::Percent(100.),
-                        display: Display::Flex,
-                        flex_direction: FlexDirection::Column,
-                        justify_content: JustifyContent::FlexStart,
-                        padding: UiRect {
-                            top: Val::Percent(10.),
-                            left: Val::Percent(4.),
+                    })
+                    .with_children(|parent| setup_main_grid_cells(parent, owner, size));
+                // Node for score and upcoming figures
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.),
+                            height: Val::Percent(100.),
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::FlexStart,
+                            padding: UiRect {
+                                top: Val::Percent(10.),
+                                left: Val::Percent(4.),
+                                ..default()
+                            },
+                            flex_wrap: FlexWrap::Wrap,
+                            row_gap: Val::Percent(4.),
+                            align_content: AlignContent::Start,
                             ..default()
                         },
-                        flex_wrap: FlexWrap::Wrap,
-                        row_gap: Val::Percent(4.),
-                        align_content: AlignContent::Start,
                         ..default()
-                    },
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent.spawn((
-                        ScoreLabel,
-                        TextBundle {
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            ScoreLabel,
+                            owner,
+                            TextBundle {
+                                text: Text::from_section(
+                                    "",
+                                    TextStyle {
+                                        font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
+                                        color: consts::FOREGROUND_COLOR,
+                                        ..default()
+                                    },
+                                ),
+                                ..default()
+                            },
+                        ));
+                        parent.spawn((
+                            CalloutLabel,
+                            owner,
+                            TextBundle {
+                                text: Text::from_section(
+                                    "",
+                                    TextStyle {
+                                        font_size: 16.,
+                                        font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
+                                        color: consts::FOREGROUND_COLOR,
+                                    },
+                                ),
+                                ..default()
+                            },
+                        ));
+                        parent.spawn(TextBundle {
                             text: Text::from_section(
-                                "",
+                                "Hold:",
                                 TextStyle {
+                                    font_size: 16.,
                                     font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
                                     color: consts::FOREGROUND_COLOR,
-                                    ..default()
                                 },
                             ),
                             ..default()
-                        },
-                    ));
-                });
+                        });
+                        parent.spawn(figure_grid_node()).with_children(|parent| {
+                            setup_figure_grid_cells(parent, (HoldFigureCell, owner))
+                        });
+                        parent.spawn(TextBundle {
+                            text: Text::from_section(
+                                "Next:",
+                                TextStyle {
+                                    font_size: 16.,
+                                    font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
+                                    color: consts::FOREGROUND_COLOR,
+                                },
+                            ),
+                            ..default()
+                        });
+                        for index in 0..consts::NEXT_FIGURES_COUNT {
+                            parent.spawn(figure_grid_node()).with_children(|parent| {
+                                setup_figure_grid_cells(parent, (NextFigureCell { index }, owner))
+                            });
+                        }
+                    });
+            }
         });
 }
 
-fn setup_main_grid_cells(spawner: &mut ChildBuilder) {
-    for x in 0..10 {
-        for y in 0..20 {
+/// Node for a small grid, which is able to fit any figure
+/// in its spawned state.
+fn figure_grid_node() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            display: Display::Grid,
+            width: Val::Px(80.),
+            height: Val::Px(40.),
+            grid_template_columns: RepeatedGridTrack::percent(4, 25.),
+            grid_template_rows: RepeatedGridTrack::percent(2, 50.),
+            grid_auto_flow: GridAutoFlow::Column,
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn setup_figure_grid_cells(spawner: &mut ChildBuilder, marker: impl Bundle + Clone) {
+    for x in 0..4 {
+        for y in 0..2 {
+            spawner.spawn((
+                marker.clone(),
+                GridPosition { x, y: 1 - y },
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(90.),
+                        height: Val::Percent(90.),
+                        ..default()
+                    },
+                    border_radius: BorderRadius::all(Val::Percent(10.)),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+/// Cells of the visible part of the board,
+/// the buffer zone above it is never drawn.
+fn setup_main_grid_cells(spawner: &mut ChildBuilder, owner: Owner, size: &BoardSize) {
+    let visible = size.visible as i32;
+    for x in 0..size.width as i32 {
+        for y in 0..visible {
             spawner.spawn((
                 GridCell,
-                GridPosition { x, y: 19 - y },
+                owner,
+                GridPosition {
+                    x,
+                    y: visible - 1 - y,
+                },
                 NodeBundle {
                     style: Style {
                         display: Display::Grid,
@@ -147,243 +430,412 @@ fn setup_main_grid_cells(spawner: &mut ChildBuilder) {
 }
 
 pub fn update_score_label(
-    mut label_query: Query<&mut Text, With<ScoreLabel>>,
-    score: Res<Score>,
+    mut label_query: Query<(&mut Text, &Owner), With<ScoreLabel>>,
+    players: Query<(Ref<Game>, &Player)>,
     asset_server: Res<AssetServer>,
 ) {
-    if !score.is_changed() {
-        return;
+    let versus = players.iter().count() > 1;
+    for (mut label, owner) in &mut label_query {
+        let Ok((game, player)) = players.get(**owner) else {
+            continue;
+        };
+        if !game.is_changed() {
+            continue;
+        }
+        let score = &game.score;
+        let time = match game.mode.time_left(game.elapsed) {
+            Some(time_left) => format_time(time_left),
+            None => format_time(game.elapsed),
+        };
+        let lines = match game.mode {
+            GameMode::Sprint => format!("{:0>2}/{}", score.lines_cleared, consts::SPRINT_LINES),
+            GameMode::Dig | GameMode::RisingTide => format!(
+                "{:0>5}\nGarbage: {}",
+                score.lines_cleared, game.garbage_left
+            ),
+            GameMode::Marathon | GameMode::Ultra => format!("{:0>5}", score.lines_cleared),
+        };
+        let title = if versus {
+            format!("PLAYER {}", player.index + 1)
+        } else {
+            game.mode.to_string()
+        };
+        *label = Text::from_section(
+            format!(
+                "{}\nTime: {}\nScore:\n{:0>12}\nLines: {}\nLevel: {:0>5}",
+                title, time, score.score, lines, score.level
+            ),
+            TextStyle {
+                font_size: 16.,
+                font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
+                color: consts::FOREGROUND_COLOR,
+            },
+        );
+    }
+}
+
+/// Draws upcoming figures in the side panels.
+/// It only updates cells when the game of their owner has changed.
+pub fn update_next_figures(
+    mut cells_query: Query<(&mut BackgroundColor, &GridPosition, &NextFigureCell, &Owner)>,
+    players: Query<Ref<Game>>,
+    settings: Res<GameSettings>,
+) {
+    for (mut color, position, cell, owner) in &mut cells_query {
+        let Ok(game) = players.get(**owner) else {
+            continue;
+        };
+        if !game.is_changed() {
+            continue;
+        }
+        let figure = game.bag.peek(consts::NEXT_FIGURES_COUNT).nth(cell.index);
+        *color = figure_cell_color(figure, position, |figure| {
+            settings.skin.figure_color(figure)
+        });
+    }
+}
+
+/// Draws held figures in the side panels.
+/// While holding is locked, the figure is dimmed.
+pub fn update_hold_figure(
+    mut cells_query: Query<(&mut BackgroundColor, &GridPosition, &Owner), With<HoldFigureCell>>,
+    players: Query<Ref<Game>>,
+    settings: Res<GameSettings>,
+) {
+    for (mut color, position, owner) in &mut cells_query {
+        let Ok(game) = players.get(**owner) else {
+            continue;
+        };
+        if !game.is_changed() {
+            continue;
+        }
+        let hold = &game.hold;
+        let skin = settings.skin;
+        *color = figure_cell_color(hold.figure.as_ref(), position, |figure| {
+            if hold.locked {
+                skin.ghost_color(figure)
+            } else {
+                skin.figure_color(figure)
+            }
+        });
+    }
+}
+
+/// Color of a preview grid cell for a figure in its spawned state.
+fn figure_cell_color(
+    fig: Option<&FigureType>,
+    position: &GridPosition,
+    fig_color: impl Fn(FigureType) -> Color,
+) -> BackgroundColor {
+    match fig {
+        Some(fig) if fig.to_dots().contains(&(position.x, position.y)) => {
+            BackgroundColor(fig_color(*fig))
+        }
+        _ => BackgroundColor(consts::BACKGROUND_COLOR),
     }
-    let mut label = label_query.single_mut();
-    *label = Text::from_section(
-        format!(
-            "Score:\n{:0>12}\nLines: {:0>5}",
-            score.score, score.lines_cleared
-        ),
-        TextStyle {
-            font_size: 16.,
-            font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
-            color: consts::FOREGROUND_COLOR,
-            ..default()
-        },
-    );
 }
 
 pub fn color_grid(
-    mut grid_query: Query<(&mut BackgroundColor, &GridPosition), With<GridCell>>,
-    play_tiles_query: Query<&GridPosition, With<PlayableTile>>,
-    board: Res<GameBoard>,
+    mut grid_query: Query<(&mut BackgroundColor, &GridPosition, &Owner), With<GridCell>>,
+    players: Query<(
+        Entity,
+        &Game,
+        &LineClear,
+        &timers::LineClearTimer,
+        Has<ToppedOut>,
+    )>,
+    top_out_timer: Res<timers::TopOutTimer>,
+    settings: Res<GameSettings>,
 ) {
-    let play_tiles = play_tiles_query.iter().collect::<HashSet<_>>();
-    for (mut color, position) in &mut grid_query {
-        if !board.check((position.x, position.y)) || play_tiles.contains(&position) {
-            *color = BackgroundColor(consts::LIGHT_FOREGROUND_COLOR);
+    let skin = settings.skin;
+    let mut figures = HashMap::new();
+    for (entity, game, line_clear, _, _) in &players {
+        // Placed figure is already a part of the board,
+        // so there is nothing to draw until the next one is spawned.
+        let tiles = if line_clear.pending {
+            (HashSet::new(), HashSet::new())
+        } else {
+            (
+                game.piece.tiles.into_iter().collect::<HashSet<_>>(),
+                // Ghost figure shows where current figure is going to land
+                // if player performs a hard drop.
+                game.ghost().into_iter().collect::<HashSet<_>>(),
+            )
+        };
+        figures.insert(entity, tiles);
+    }
+    for (mut color, position, owner) in &mut grid_query {
+        let (
+            Ok((_, game, line_clear, line_clear_timer, topped_out)),
+            Some((play_tiles, ghost_tiles)),
+        ) = (players.get(**owner), figures.get(&**owner))
+        else {
+            continue;
+        };
+        // Board of the topped out player is covered
+        // from the bottom to the top.
+        let covered = top_out_timer.fraction() * game.size.visible as f32;
+        if topped_out && (position.y as f32) < covered {
+            *color = BackgroundColor(skin.top_out_color());
+            continue;
+        }
+        // Cleared rows dissolve from the center to the edges.
+        let half_width = game.size.width as f32 / 2.;
+        let dissolved = line_clear_timer.fraction() * half_width;
+        let cell = game.board.cell((position.x, position.y));
+        if line_clear.rows.contains(&(position.y as usize)) {
+            let distance = (position.x as f32 + 0.5 - half_width).abs();
+            *color = if distance < dissolved {
+                BackgroundColor(consts::BACKGROUND_COLOR)
+            } else {
+                BackgroundColor(skin.line_clear_color())
+            };
+        } else if play_tiles.contains(&(position.x, position.y)) {
+            *color = BackgroundColor(skin.figure_color(game.piece.figure));
+        } else if cell.is_occupied() {
+            *color = BackgroundColor(skin.cell_color(cell));
+        } else if ghost_tiles.contains(&(position.x, position.y)) {
+            *color = BackgroundColor(skin.ghost_color(game.piece.figure));
         } else {
             *color = BackgroundColor(consts::BACKGROUND_COLOR);
         }
     }
 }
 
+/// Sends control events for keys of every player,
+/// except for horizontal moves, which are handled by [`auto_shift`].
 pub fn handle_inputs(
     key: Res<ButtonInput<KeyCode>>,
-    mut soft_drop: ResMut<SoftDrop>,
-    mut horizontall_moves: EventWriter<events::MoveTetronomioHorizontally>,
-    mut rot_events: EventWriter<events::RotateTetronomio>,
-    mut hard_drop_events: EventWriter<events::HardDrop>,
+    players: Query<(Entity, &Controls), Without<Autoplayer>>,
+    mut controls: ControlWriters,
 ) {
-    if key.just_pressed(KeyCode::ArrowLeft) || key.just_pressed(KeyCode::KeyH) {
-        horizontall_moves.send(events::MoveTetronomioHorizontally { right: false });
-    }
-    if key.just_pressed(KeyCode::ArrowRight) || key.just_pressed(KeyCode::KeyL) {
-        horizontall_moves.send(events::MoveTetronomioHorizontally { right: true });
-    }
-    if key.just_pressed(KeyCode::Space) {
-        hard_drop_events.send(events::HardDrop);
+    for (player, keys) in &players {
+        let just_pressed = |keys: &[KeyCode]| key.any_just_pressed(keys.iter().copied());
+        if just_pressed(keys.hard_drop) {
+            controls.send(player, ReplayInput::HardDrop);
+        }
+        if just_pressed(keys.rotate_cw) {
+            controls.send(player, ReplayInput::Rotate { clockwise: true });
+        }
+        if just_pressed(keys.rotate_ccw) {
+            controls.send(player, ReplayInput::Rotate { clockwise: false });
+        }
+        if just_pressed(keys.hold) {
+            controls.send(player, ReplayInput::Hold);
+        }
+
+        if just_pressed(keys.soft_drop) {
+            controls.send(player, ReplayInput::SoftDrop { active: true });
+        }
+        if key.any_just_released(keys.soft_drop.iter().copied())
+            && !key.any_pressed(keys.soft_drop.iter().copied())
+        {
+            controls.send(player, ReplayInput::SoftDrop { active: false });
+        }
     }
-    if key.just_pressed(KeyCode::ArrowUp) || key.just_pressed(KeyCode::KeyK) {
-        let shift_pressed = key.pressed(KeyCode::ShiftLeft) || key.pressed(KeyCode::ShiftRight);
-        rot_events.send(events::RotateTetronomio {
-            // If shift is pressed, we rotate counter-clockwise.
-            clockwise: !shift_pressed,
-        });
+}
+
+/// Sends horizontal moves for held left and right keys.
+///
+/// A press moves the figure once. If the key is held longer than
+/// the auto-shift delay, the move is repeated at the repeat rate.
+/// With zero repeat rate, the figure goes straight to the wall.
+/// When both directions are held, the last pressed one wins and
+/// releasing it charges the delay for the other one again.
+///
+/// Moves into a wall or locked tiles are not sent,
+/// so they don't end up in the replay.
+pub fn auto_shift(
+    key: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    tick: Res<GameTick>,
+    settings: Res<GameSettings>,
+    mut players: Query<(Entity, &Controls, &Game, &mut AutoShift), Without<Autoplayer>>,
+    mut controls: ControlWriters,
+) {
+    for (player, keys, game, mut auto_shift) in &mut players {
+        // The game is only updated by the fixed timestep, so moves
+        // sent in the frames since the last tick are not applied yet.
+        if auto_shift.tick != tick.0 {
+            auto_shift.tick = tick.0;
+            auto_shift.pending = 0;
+        }
+        let room = |right: bool, pending: i32| {
+            let room = game.horizontal_room(right) as i32;
+            let shifted = if right { pending } else { -pending };
+            (room - shifted).max(0) as u32
+        };
+        let direction_keys = |right: bool| {
+            let keys = if right { keys.right } else { keys.left };
+            keys.iter().copied()
+        };
+        let held = |right: bool| key.any_pressed(direction_keys(right));
+        let pressed = if key.any_just_pressed(direction_keys(true)) {
+            Some(true)
+        } else if key.any_just_pressed(direction_keys(false)) {
+            Some(false)
+        } else {
+            None
+        };
+        if let Some(right) = pressed {
+            if room(right, auto_shift.pending) > 0 {
+                controls.send(player, ReplayInput::Move { right });
+                auto_shift.pending += if right { 1 } else { -1 };
+            }
+            auto_shift.right = Some(right);
+            auto_shift.delay = Timer::new(settings.auto_shift_delay, TimerMode::Once);
+        } else if let Some(right) = auto_shift.right {
+            if !held(right) {
+                auto_shift.right = if held(!right) { Some(!right) } else { None };
+                auto_shift.delay = Timer::new(settings.auto_shift_delay, TimerMode::Once);
+            }
+        }
+
+        let Some(right) = auto_shift.right else {
+            continue;
+        };
+        if pressed.is_some() || !auto_shift.delay.tick(time.delta()).finished() {
+            continue;
+        }
+        let room = room(right, auto_shift.pending);
+        let moves = if settings.auto_repeat_rate.is_zero() {
+            room
+        } else if auto_shift.delay.just_finished() {
+            auto_shift.repeat = Timer::new(settings.auto_repeat_rate, TimerMode::Repeating);
+            1
+        } else {
+            auto_shift
+                .repeat
+                .tick(time.delta())
+                .times_finished_this_tick()
+        }
+        .min(room);
+        for _ in 0..moves {
+            controls.send(player, ReplayInput::Move { right });
+        }
+        auto_shift.pending += if right { moves as i32 } else { -(moves as i32) };
     }
+}
 
-    if key.pressed(KeyCode::ArrowDown) || key.pressed(KeyCode::KeyJ) {
-        if !soft_drop.active {
-            soft_drop.active = true;
+pub fn toggle_soft_drop(
+    mut event_reader: EventReader<events::SoftDropToggled>,
+    mut players: Query<&mut SoftDrop>,
+) {
+    for event in event_reader.read() {
+        if let Ok(mut soft_drop) = players.get_mut(event.player) {
+            soft_drop.active = event.active;
         }
-    } else if soft_drop.active {
-        soft_drop.active = false;
     }
 }
 
 pub fn lockdown_tiles(
-    mut timer: ResMut<LockdownTimer>,
+    mut players: Query<(Entity, &mut Game, &LineClear)>,
     time: Res<Time>,
-    mut board: ResMut<GameBoard>,
-    mut tiles: Query<(&mut FigureType, &mut Rotation, &mut GridPosition), With<PlayableTile>>,
-    mut state: ResMut<NextState<GameState>>,
-    mut bag: ResMut<TetroBag>,
+    mut placed_events: EventWriter<events::FigurePlaced>,
+    mut game_over_events: EventWriter<events::GameOver>,
 ) {
-    if timer.tick(time.delta()).just_finished() {
-        let can_move_down = tiles
-            .iter()
-            .all(|(_, _, pos)| board.check((pos.x, pos.y - 1)));
-        if can_move_down {
-            return;
+    for (player, mut game, line_clear) in &mut players {
+        if line_clear.pending {
+            continue;
         }
+        if !game.tick_lockdown(time.delta()) || game.can_move_down() {
+            continue;
+        }
+        // Figure locked out of sight ends the game,
+        // even if it would clear some lines.
+        let locked_out = game.locked_out();
         // Here we should lock the tiles in place.
-        let next_figure = bag.draw_next();
-        let new_dots = next_figure.to_dots();
-        for (i, (mut fig_type, mut rotation, mut grid_pos)) in tiles.iter_mut().enumerate() {
-            board.occupy((grid_pos.x, grid_pos.y));
-            *fig_type = next_figure;
-            *rotation = Rotation::R0;
-            *grid_pos = GridPosition {
-                x: new_dots[i].0 + consts::START_X_POSITION,
-                y: new_dots[i].1 + consts::START_Y_POSITION,
-            };
-            if !board.check((grid_pos.x, grid_pos.y)) {
-                state.set(GameState::GameOver);
-            }
+        // Next figure is spawned after filled lines are cleared.
+        let t_spin = game.lock();
+        if locked_out {
+            game_over_events.send(events::GameOver {
+                player,
+                reason: TopOut::LockOut,
+            });
+        } else {
+            placed_events.send(events::FigurePlaced { player, t_spin });
         }
     }
 }
 
 pub fn rotate_tetronomio(
     mut event_reader: EventReader<events::RotateTetronomio>,
-    mut playable_tiles: Query<(&mut GridPosition, &FigureType, &mut Rotation), With<PlayableTile>>,
-    mut lockdown_timer: ResMut<timers::LockdownTimer>,
-    board: Res<GameBoard>,
+    mut players: Query<(&mut Game, &LineClear)>,
 ) {
-    // We iterate over all rotation events,
-    // but actually there should be no more than 1
-    // event at a time. Because no other systems except for input are sending this event.
+    // Every player can rotate at most once per tick,
+    // but events of both players arrive together.
     for event in event_reader.read() {
-        // Offsets of the figure bounding box.
-        let x_offset = playable_tiles
-            .iter()
-            .map(|(pos, _, _)| pos.x as i32)
-            .min()
-            .unwrap_or(0);
-        let y_offset = playable_tiles
-            .iter()
-            .map(|(pos, _, _)| pos.y as i32)
-            .min()
-            .unwrap_or(0);
-        let Some((_, fig, rot)) = playable_tiles.iter().next() else {
-            // This statement should never be reached.
-            // If it does, it means that there are no playable tiles (which should never be true).
+        let Ok((mut game, line_clear)) = players.get_mut(event.player) else {
             continue;
         };
-        let mut valid_pos = HashMap::<(i32, i32), (i32, i32)>::with_capacity(4);
-        let mut can_move = false;
-        let next_rot = if event.clockwise {
-            rot.right()
-        } else {
-            rot.left()
-        };
-        let (center_x, center_y) = fig.center_point(&rot);
-        // Here we iterate over rotation tests
-        // and try to fit the piece in.
-        for (test_x, test_y) in fig.tests_for_rot(&rot, &next_rot) {
-            // For each test, we iterate over all tiles controlled by a player.
-            // If we find a valid position for all tiles, we can move the figure.
-            let mut has_error = false;
-            for (tile, _, _) in playable_tiles.iter() {
-                // Here we subtract the offset of the bounding box and the center point
-                // to get the relative position of the tile to the center point.
-                let (new_x, new_y) = if event.clockwise {
-                    rotate_right(tile.x - x_offset - center_x, tile.y - y_offset - center_y)
-                } else {
-                    rotate_left(tile.x - x_offset - center_x, tile.y - y_offset - center_y)
-                };
-                // Here we add the offset of the bounding box and the center point back
-                // to get the absolute position of the tile.
-                // And also we add the test offset to the tile position to perform
-                // wall-kick if possible.
-                let new_mapped_x = new_x + x_offset + center_x + test_x;
-                let new_mapped_y = new_y + y_offset + center_y + test_y;
-                if board.check((new_mapped_x, new_mapped_y)) {
-                    // If this point has passed the test, we add it to our
-                    // mapping. Which maps original position to the new position.
-                    valid_pos.insert((tile.x, tile.y), (new_mapped_x, new_mapped_y));
-                    continue;
-                }
-                // If you reach to this point, then this test is failed, and we should
-                // continue to the next test.
-                has_error = true;
-            }
-            // This means that all points can move to desired location.
-            // We move if that's true.
-            if !has_error {
-                can_move = true;
-                break;
-            }
-        }
-        // We cannot perform any rotation, so we do nothing.
-        if !can_move {
+        // Controls are suspended while filled lines are being cleared.
+        // We also cannot perform every rotation, and then we do nothing.
+        if line_clear.pending || !game.rotate(event.clockwise) {
             continue;
         }
-        if !lockdown_timer.finished() {
-            lockdown_timer.reset();
+        game.use_lock_reset();
+    }
+}
+
+/// Swaps current figure with the held one.
+///
+/// Swapped in figure is placed at the spawn position
+/// and starts falling from scratch, without a pending lockdown.
+pub fn hold_figure(
+    mut event_reader: EventReader<events::HoldFigure>,
+    mut players: Query<(&mut Game, &mut timers::GravityTimer, &LineClear)>,
+    mut game_over_events: EventWriter<events::GameOver>,
+) {
+    for event in event_reader.read() {
+        let Ok((mut game, mut gravity_timer, line_clear)) = players.get_mut(event.player) else {
+            continue;
+        };
+        if line_clear.pending {
+            continue;
         }
-        // Here we move the tiles to their new locations, also updating rotation data.
-        for (mut tile, _, mut rot) in playable_tiles.iter_mut() {
-            *rot = next_rot;
-            if let Some((new_x, new_y)) = valid_pos.get(&(tile.x, tile.y)) {
-                tile.x = *new_x;
-                tile.y = *new_y;
+        match game.hold() {
+            None => continue,
+            Some(false) => {
+                game_over_events.send(events::GameOver {
+                    player: event.player,
+                    reason: TopOut::BlockOut,
+                });
+                continue;
             }
+            Some(true) => {}
         }
+        gravity_timer.reset();
     }
 }
 
+/// Moves the figure, resetting the lockdown
+/// the same way as rotations do.
 pub fn move_tetronomio_horizontally(
     mut event_reader: EventReader<events::MoveTetronomioHorizontally>,
-    mut playable_tiles: Query<(&mut GridPosition, &FigureType, &mut Rotation), With<PlayableTile>>,
-    board: Res<GameBoard>,
+    mut players: Query<(&mut Game, &LineClear)>,
 ) {
-    // We iterate over all rotation events,
-    // but actually there should be no more than 1
-    // event at a time. Because no other systems except for input are sending this event.
+    // Auto-repeat may send several moves at once.
     for event in event_reader.read() {
-        let mut delta = -1;
-        // If we are moving to the right,
-        // we change direction to increasing x.
-        if event.right {
-            delta = 1;
-        }
-        let can_move = playable_tiles
-            .iter()
-            .all(|(pos, _, _)| board.check((pos.x + delta, pos.y)));
-        if !can_move {
+        let Ok((mut game, line_clear)) = players.get_mut(event.player) else {
+            continue;
+        };
+        if line_clear.pending || !game.move_horizontally(event.right) {
             continue;
         }
-        for (mut tile, _, _) in playable_tiles.iter_mut() {
-            tile.x += delta;
-        }
+        game.use_lock_reset();
     }
 }
 
 pub fn hard_drop(
-    mut playable_tiles: Query<&mut GridPosition, With<PlayableTile>>,
-    board: Res<GameBoard>,
-    mut score: ResMut<Score>,
+    mut event_reader: EventReader<events::HardDrop>,
+    mut players: Query<(&mut Game, &LineClear)>,
 ) {
-    let all_xs = playable_tiles.iter().map(|pos| pos.x);
-    // Here we find maximum y for all x positions,
-    // So we know exactly where we should place our tile.
-    let max_board_y = board.get_max_y_many(all_xs) as i32;
-    let bb_y = playable_tiles.iter().map(|pos| pos.y).min().unwrap_or(0);
-    let mut grid_delta = 0;
-    for mut pos in playable_tiles.iter_mut() {
-        let new_y = max_board_y + 1 + (pos.y - bb_y);
-        grid_delta += (new_y - pos.y).abs() as usize;
-        pos.y = new_y;
-    }
-    if grid_delta > 0 {
-        score.score += grid_delta * 3;
+    for event in event_reader.read() {
+        if let Ok((mut game, line_clear)) = players.get_mut(event.player) {
+            if !line_clear.pending {
+                game.hard_drop();
+            }
+        }
     }
 }
 
@@ -395,65 +847,198 @@ pub fn hard_drop(
 ///
 /// If we cannot move down any further, we start the lockdown timer.
 pub fn gravity(
-    mut tiles: Query<&mut GridPosition, With<PlayableTile>>,
-    board: Res<GameBoard>,
+    mut players: Query<(&mut Game, &mut timers::GravityTimer, &SoftDrop, &LineClear)>,
     time: Res<Time>,
-    mut gravity_timer: ResMut<timers::GravityTimer>,
-    mut lockdown_timer: ResMut<timers::LockdownTimer>,
-    soft_drop: Res<SoftDrop>,
-    mut score: ResMut<Score>,
 ) {
-    let mulitplier = if soft_drop.active {
-        consts::SOFT_DROP_MULTIPLER
-    } else {
-        1
-    };
-    if !gravity_timer.tick(time.delta() * mulitplier).finished() {
-        return;
-    }
-    let can_move_down = tiles.iter().all(|pos| board.check((pos.x, pos.y - 1)));
-    if !can_move_down {
-        if lockdown_timer.finished() {
-            lockdown_timer.reset();
+    for (mut game, mut gravity_timer, soft_drop, line_clear) in &mut players {
+        // Gravity is suspended while filled lines are being cleared.
+        if line_clear.pending {
+            continue;
         }
-        return;
-    }
-    if soft_drop.active {
-        score.score += 1;
+        let mulitplier = if soft_drop.active {
+            consts::SOFT_DROP_MULTIPLER
+        } else {
+            1
+        };
+        if !gravity_timer.tick(time.delta() * mulitplier).finished() {
+            continue;
+        }
+        if !game.can_move_down() {
+            game.start_lockdown();
+            continue;
+        }
+        // If the lockdown timer is running,
+        // but we have space to fall, we should cancel this timer.
+        game.cancel_lockdown();
+        game.step_down(soft_drop.active);
     }
-    // If the lockdown timer is running,
-    // but we have space to fall, we should cancel this timer,
-    // by supplying it with ridiculously big value.
-    if !lockdown_timer.finished() {
-        lockdown_timer.tick(Duration::from_secs(10));
+}
+
+/// Starts the line clear animation for rows filled by the placed figure.
+pub fn start_line_clear(
+    mut placed_events: EventReader<events::FigurePlaced>,
+    mut players: Query<(&Game, &mut LineClear, &mut timers::LineClearTimer)>,
+) {
+    for event in placed_events.read() {
+        let Ok((game, mut line_clear, mut timer)) = players.get_mut(event.player) else {
+            continue;
+        };
+        *line_clear = LineClear {
+            pending: true,
+            rows: game.board.full_rows(),
+            t_spin: event.t_spin,
+        };
+        timer.reset();
     }
-    for mut pos in tiles.iter_mut() {
-        if !board.check((pos.x, pos.y - 1)) {
+}
+
+/// Clears filled lines after the animation has finished,
+/// shows a callout for special clears and spawns the next figure.
+///
+/// Garbage for the opponents is cancelled against the garbage
+/// the player has received. Received garbage rises
+/// only after a placement which hasn't cleared any lines.
+///
+/// Placements without filled rows are finished at once.
+pub fn collapse_lines(
+    mut players: Query<(
+        Entity,
+        &mut Game,
+        &mut LineClear,
+        &mut timers::LineClearTimer,
+        &mut timers::GravityTimer,
+        &mut PendingGarbage,
+    )>,
+    time: Res<Time>,
+    mut callouts: EventWriter<events::Callout>,
+    mut garbage_events: EventWriter<events::SendGarbage>,
+    mut game_over_events: EventWriter<events::GameOver>,
+) {
+    for (player, mut game, mut line_clear, mut timer, mut gravity_timer, mut pending) in
+        &mut players
+    {
+        if !line_clear.pending
+            || (!line_clear.rows.is_empty() && !timer.tick(time.delta()).finished())
+        {
             continue;
         }
+        let result = game.clear_lines(line_clear.t_spin);
+        *line_clear = LineClear::default();
+        if result.cleared > 0 {
+            gravity_timer.set_duration(game.score.gravity_interval());
+        }
+        if let Some(text) = callout_text(&result) {
+            callouts.send(events::Callout { player, text });
+        }
+        let garbage = result.garbage();
+        let cancelled = garbage.min(**pending);
+        **pending -= cancelled;
+        if garbage > cancelled {
+            garbage_events.send(events::SendGarbage {
+                player,
+                rows: garbage - cancelled,
+            });
+        }
+        if result.cleared == 0 && **pending > 0 {
+            let rows = std::mem::take(&mut **pending);
+            // The figure is already locked, so there is nothing to lift.
+            if !game.push_garbage_rows(rows) {
+                game_over_events.send(events::GameOver {
+                    player,
+                    reason: TopOut::PushOut,
+                });
+                continue;
+            }
+        }
+        if !game.spawn_next() {
+            game_over_events.send(events::GameOver {
+                player,
+                reason: TopOut::BlockOut,
+            });
+        }
+    }
+}
 
-        pos.y -= 1;
+/// Queues garbage sent by a player for all other players.
+pub fn receive_garbage(
+    mut garbage_events: EventReader<events::SendGarbage>,
+    mut players: Query<(Entity, &mut PendingGarbage)>,
+) {
+    for event in garbage_events.read() {
+        for (player, mut pending) in &mut players {
+            if player != event.player {
+                **pending += event.rows;
+            }
+        }
+    }
+}
+
+/// Text describing t-spins, tetrises, combos and back-to-back clears.
+fn callout_text(result: &ClearResult) -> Option<String> {
+    let mut callout = Vec::new();
+    if result.back_to_back {
+        callout.push(String::from("B2B"));
+    }
+    match result.t_spin {
+        Some(TSpin::Mini) => callout.push(String::from("T-SPIN MINI")),
+        Some(TSpin::Full) => callout.push(String::from("T-SPIN")),
+        None => {}
+    }
+    match (result.t_spin, result.cleared) {
+        (None, 4) => callout.push(String::from("TETRIS")),
+        (Some(_), 1) => callout.push(String::from("SINGLE")),
+        (Some(_), 2) => callout.push(String::from("DOUBLE")),
+        (Some(_), 3) => callout.push(String::from("TRIPLE")),
+        _ => {}
+    }
+    if result.combo > 0 {
+        callout.push(format!("\nCOMBO x{}", result.combo));
+    }
+    if callout.is_empty() {
+        None
+    } else {
+        Some(callout.join(" "))
     }
 }
 
-pub fn clear_lines(
-    mut board: ResMut<GameBoard>,
-    lockdown_timer: Res<LockdownTimer>,
-    mut score: ResMut<Score>,
+pub fn show_callout(
+    mut event_reader: EventReader<events::Callout>,
+    mut label_query: Query<(&mut Text, &Owner), With<CalloutLabel>>,
+    mut players: Query<&mut timers::CalloutTimer>,
 ) {
-    // We only check for lines when the lockdown timer is
-    // finished. Because we don't want to clear lines while
-    // the tiles are moving down.
-    if !lockdown_timer.finished() {
-        return;
+    for event in event_reader.read() {
+        for (mut label, owner) in label_query.iter_mut() {
+            if **owner != event.player {
+                continue;
+            }
+            if let Some(section) = label.sections.first_mut() {
+                section.value = event.text.clone();
+            }
+        }
+        if let Ok(mut timer) = players.get_mut(event.player) {
+            timer.reset();
+        }
     }
-    let cleared = board.clear_lines();
-    if cleared > 0 {
-        let mut score_delta = 100;
-        for _ in 1..cleared {
-            score_delta += 200;
+}
+
+/// Removes callouts from the side panels
+/// after they have been shown for a while.
+pub fn hide_callout(
+    mut label_query: Query<(&mut Text, &Owner), With<CalloutLabel>>,
+    mut players: Query<(Entity, &mut timers::CalloutTimer)>,
+    time: Res<Time>,
+) {
+    for (player, mut timer) in &mut players {
+        if !timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        for (mut label, owner) in label_query.iter_mut() {
+            if **owner != player {
+                continue;
+            }
+            if let Some(section) = label.sections.first_mut() {
+                section.value.clear();
+            }
         }
-        score.score += score_delta;
-        score.lines_cleared += cleared;
     }
 }
@@ -1,7 +1,17 @@
 use bevy::prelude::*;
 
-#[derive(Debug, Clone, Deref, DerefMut, Resource)]
+#[derive(Debug, Clone, Deref, DerefMut, Component)]
 pub struct GravityTimer(pub Timer);
 
+#[derive(Debug, Clone, Deref, DerefMut, Component)]
+pub struct CalloutTimer(pub Timer);
+
+#[derive(Debug, Clone, Deref, DerefMut, Component)]
+pub struct LineClearTimer(pub Timer);
+
+#[derive(Debug, Clone, Deref, DerefMut, Component)]
+pub struct RisingTideTimer(pub Timer);
+
+/// Top out animation, shown before the game over screen.
 #[derive(Debug, Clone, Deref, DerefMut, Resource)]
-pub struct LockdownTimer(pub Timer);
+pub struct TopOutTimer(pub Timer);
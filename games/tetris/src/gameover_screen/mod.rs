@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use systems::{focused_button_decoration, on_select_item};
 
 use crate::{
-    state::GameState,
+    state::{Demo, GameState},
     utils::systems::{despawn_screen, vert_menu_controls},
 };
 mod components;
@@ -12,19 +12,29 @@ pub struct GameoverScreenPlugin;
 
 impl Plugin for GameoverScreenPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::GameOver), systems::render_screen)
-            .add_systems(
-                Update,
-                (
-                    vert_menu_controls,
-                    on_select_item,
-                    focused_button_decoration,
-                )
-                    .run_if(in_state(GameState::GameOver)),
+        app.add_systems(
+            OnEnter(GameState::GameOver),
+            (
+                systems::render_screen.run_if(not(demo_is_playing)),
+                systems::end_demo.run_if(demo_is_playing),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                vert_menu_controls,
+                on_select_item,
+                focused_button_decoration,
             )
-            .add_systems(
-                OnExit(GameState::GameOver),
-                despawn_screen::<components::OnMenuScreen>,
-            );
+                .run_if(in_state(GameState::GameOver)),
+        )
+        .add_systems(
+            OnExit(GameState::GameOver),
+            despawn_screen::<components::OnMenuScreen>,
+        );
     }
 }
+
+fn demo_is_playing(demo: Res<Demo>) -> bool {
+    **demo
+}
@@ -2,13 +2,45 @@ use bevy::prelude::*;
 
 use crate::{
     consts,
-    state::{GameState, Score},
+    engine::{format_time, GameMode},
+    state::{Game, GameState, Player, ToppedOut},
     utils::components::HasFocus,
 };
 
 use super::components::{ButtonAction, OnMenuScreen};
 
-pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>, score: Res<Score>) {
+pub fn render_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    players: Query<(&Player, &Game, Option<&ToppedOut>)>,
+) {
+    let mut players = players.iter().collect::<Vec<_>>();
+    players.sort_by_key(|(player, _, _)| player.index);
+    let Some((_, first, _)) = players.first() else {
+        return;
+    };
+    let mut results = vec![format!("Mode: {}", first.mode)];
+    let title = if let [(_, game, topped_out)] = players.as_slice() {
+        if let Some(topped_out) = topped_out {
+            results.push(topped_out.reason.to_string());
+        }
+        results.extend(game_results(game));
+        game_title(game).to_string()
+    } else {
+        for (player, game, topped_out) in &players {
+            match topped_out {
+                Some(topped_out) => results.push(format!(
+                    "PLAYER {} - {}",
+                    player.index + 1,
+                    topped_out.reason
+                )),
+                None => results.push(format!("PLAYER {}", player.index + 1)),
+            }
+            results.extend(game_results(game));
+        }
+        versus_title(&players)
+    };
+    results.push(format!("Seed: {}", first.seed));
     let text_style = TextStyle {
         font_size: 16.,
         font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
@@ -35,7 +67,7 @@ pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>, sco
         .with_children(|parent| {
             parent.spawn(TextBundle {
                 text: Text::from_section(
-                    "GAME OVER",
+                    title,
                     TextStyle {
                         font_size: 32.,
                         ..text_style.clone()
@@ -43,17 +75,12 @@ pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>, sco
                 ),
                 ..default()
             });
-            parent.spawn(TextBundle {
-                text: Text::from_section(format!("Score: {}", score.score), text_style.clone()),
-                ..default()
-            });
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    format!("Lines cleared: {}", score.lines_cleared),
-                    text_style.clone(),
-                ),
-                ..default()
-            });
+            for line in results {
+                parent.spawn(TextBundle {
+                    text: Text::from_section(line, text_style.clone()),
+                    ..default()
+                });
+            }
             parent
                 .spawn(NodeBundle {
                     style: Style {
@@ -106,6 +133,11 @@ pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>, sco
         });
 }
 
+/// Demo game goes straight back to the start menu.
+pub fn end_demo(mut app_state: ResMut<NextState<GameState>>) {
+    app_state.set(GameState::StartMenu);
+}
+
 pub fn focused_button_decoration(
     mut buttons: Query<(&mut BorderColor, Option<&HasFocus>), With<Button>>,
 ) {
@@ -135,3 +167,87 @@ pub fn on_select_item(
         }
     }
 }
+
+fn game_title(game: &Game) -> &'static str {
+    let finished = game.goal_reached();
+    match game.mode {
+        GameMode::Sprint if finished => "COMPLETE",
+        GameMode::Ultra if finished => "TIME UP",
+        GameMode::Dig | GameMode::RisingTide if finished => "COMPLETE",
+        _ => "GAME OVER",
+    }
+}
+
+/// Results of a single game, which depend on its mode.
+fn game_results(game: &Game) -> Vec<String> {
+    let score = &game.score;
+    let finished = game.goal_reached();
+    let mut results = Vec::new();
+    match game.mode {
+        GameMode::Marathon => {
+            results.push(format!("Score: {}", score.score));
+            results.push(format!("Lines cleared: {}", score.lines_cleared));
+            results.push(format!("Level: {}", score.level));
+        }
+        GameMode::Sprint => {
+            if finished {
+                results.push(format!("Time: {}", format_time(game.elapsed)));
+            }
+            results.push(format!(
+                "Lines cleared: {}/{}",
+                score.lines_cleared.min(consts::SPRINT_LINES),
+                consts::SPRINT_LINES
+            ));
+        }
+        GameMode::Dig | GameMode::RisingTide => {
+            if finished {
+                results.push(format!("Time: {}", format_time(game.elapsed)));
+            } else {
+                results.push(format!("Garbage left: {}", game.garbage_left));
+            }
+            results.push(format!("Lines cleared: {}", score.lines_cleared));
+        }
+        GameMode::Ultra => {
+            results.push(format!("Score: {}", score.score));
+            results.push(format!("Lines cleared: {}", score.lines_cleared));
+        }
+    }
+    results
+}
+
+/// Names the winner of a versus game.
+///
+/// Players who have topped out lose. Otherwise the game has ended
+/// with a goal, which is won by the players who have reached it,
+/// or by the best score when the time is up in the ultra mode.
+fn versus_title(players: &[(&Player, &Game, Option<&ToppedOut>)]) -> String {
+    let alive = players
+        .iter()
+        .filter(|(_, _, topped_out)| topped_out.is_none())
+        .collect::<Vec<_>>();
+    let winners = if alive.len() < players.len() {
+        alive
+    } else if players
+        .iter()
+        .all(|(_, game, _)| game.mode == GameMode::Ultra)
+    {
+        let best = players
+            .iter()
+            .map(|(_, game, _)| game.score.score)
+            .max()
+            .unwrap_or(0);
+        alive
+            .into_iter()
+            .filter(|(_, game, _)| game.score.score == best)
+            .collect()
+    } else {
+        alive
+            .into_iter()
+            .filter(|(_, game, _)| game.goal_reached())
+            .collect()
+    };
+    match winners.as_slice() {
+        [(player, _, _)] => format!("PLAYER {} WINS", player.index + 1),
+        _ => String::from("DRAW"),
+    }
+}
@@ -2,8 +2,10 @@
 use bevy::{prelude::*, window::WindowResolution};
 
 pub mod consts;
+pub mod engine;
 pub mod game_screen;
 pub mod gameover_screen;
+pub mod skin;
 pub mod start_menu;
 pub mod state;
 pub mod utils;
@@ -29,9 +31,12 @@ fn main() {
         }))
         .add_systems(Startup, setup_camera)
         .add_systems(Update, exit_game)
-        .insert_resource(state::Score::default())
+        .insert_resource(state::GameSettings::default())
+        .insert_resource(state::Playback::default())
+        .insert_resource(state::Demo::default())
         .insert_resource(state::Debug::default())
         .init_state::<state::GameState>()
+        .add_sub_state::<state::PauseState>()
         .add_plugins(cgc_lib::CGCCommonAssetsPlugin)
         .add_plugins(start_menu::StartMenu)
         .add_plugins(game_screen::GameScreenPlugin)
@@ -61,7 +66,7 @@ fn exit_game(
         exit_writer.send(AppExit::Success);
     }
     #[cfg(debug_assertions)]
-    if keys.just_pressed(KeyCode::KeyD) {
+    if keys.just_pressed(KeyCode::F3) {
         debug_res.enabled = !debug_res.enabled;
     }
 }
@@ -0,0 +1,90 @@
+use std::fmt;
+
+use bevy::color::{Alpha, Color};
+
+use crate::{
+    consts,
+    engine::{Cell, FigureType},
+};
+
+/// Color theme of the board and the side panel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Skin {
+    // Monochrome green, like the original game.
+    #[default]
+    Classic,
+    // Colors of the figures from the guideline.
+    Guideline,
+    // White tiles on black, garbage is gray.
+    HighContrast,
+}
+
+impl Skin {
+    pub fn all() -> [Skin; 3] {
+        [Skin::Classic, Skin::Guideline, Skin::HighContrast]
+    }
+
+    /// Color of a tile of a figure.
+    pub fn figure_color(&self, figure: FigureType) -> Color {
+        match self {
+            Skin::Classic => consts::LIGHT_FOREGROUND_COLOR,
+            Skin::Guideline => match figure {
+                FigureType::O => Color::srgb(0.95, 0.85, 0.),
+                FigureType::I => Color::srgb(0., 0.85, 0.95),
+                FigureType::S => Color::srgb(0.1, 0.85, 0.1),
+                FigureType::Z => Color::srgb(0.95, 0.1, 0.1),
+                FigureType::L => Color::srgb(0.95, 0.55, 0.),
+                FigureType::J => Color::srgb(0.15, 0.3, 0.95),
+                FigureType::T => Color::srgb(0.65, 0.15, 0.85),
+            },
+            Skin::HighContrast => Color::WHITE,
+        }
+    }
+
+    /// Color of a board cell, empty cells are not drawn.
+    pub fn cell_color(&self, cell: Cell) -> Color {
+        match (self, cell) {
+            (_, Cell::Empty) => consts::BACKGROUND_COLOR,
+            (_, Cell::Figure(figure)) => self.figure_color(figure),
+            (Skin::Classic, Cell::Garbage) => consts::LIGHT_FOREGROUND_COLOR,
+            (Skin::Guideline, Cell::Garbage) => Color::srgb(0.5, 0.5, 0.5),
+            (Skin::HighContrast, Cell::Garbage) => Color::srgb(0.6, 0.6, 0.6),
+        }
+    }
+
+    /// Color of the ghost figure and of the locked hold slot.
+    pub fn ghost_color(&self, figure: FigureType) -> Color {
+        match self {
+            Skin::Classic => consts::DIMMED_FOREGROUND_COLOR,
+            Skin::Guideline => self.figure_color(figure).with_alpha(0.35),
+            Skin::HighContrast => Color::srgb(0.35, 0.35, 0.35),
+        }
+    }
+
+    /// Color of the rows being cleared.
+    pub fn line_clear_color(&self) -> Color {
+        match self {
+            Skin::Classic => consts::FOREGROUND_COLOR,
+            Skin::Guideline | Skin::HighContrast => Color::WHITE,
+        }
+    }
+
+    /// Color covering the board of a topped out player.
+    pub fn top_out_color(&self) -> Color {
+        match self {
+            Skin::Classic => consts::DIMMED_FOREGROUND_COLOR,
+            Skin::Guideline | Skin::HighContrast => Color::srgb(0.3, 0.3, 0.3),
+        }
+    }
+}
+
+impl fmt::Display for Skin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Skin::Classic => "CLASSIC",
+            Skin::Guideline => "GUIDELINE",
+            Skin::HighContrast => "HIGH CONTRAST",
+        };
+        f.write_str(name)
+    }
+}
@@ -1,10 +1,38 @@
 use bevy::prelude::*;
 
+use crate::engine::GameMode;
+
 #[derive(Component, Debug)]
 pub struct OnMenuScreen;
 
 #[derive(Component, Debug)]
 pub enum ButtonAction {
     Quit,
-    StartGame,
+    StartGame(GameMode),
+    ChangeBoard,
+    ChangePlayers,
+    ChangeSkin,
+    ChangeAutoShiftDelay,
+    ChangeAutoRepeatRate,
+    WatchReplay,
 }
+
+/// Text of the button showing the selected board size.
+#[derive(Component, Debug)]
+pub struct BoardLabel;
+
+/// Text of the button showing the number of players.
+#[derive(Component, Debug)]
+pub struct PlayersLabel;
+
+/// Text of the button showing the selected skin.
+#[derive(Component, Debug)]
+pub struct SkinLabel;
+
+/// Text of the button showing the selected auto-shift delay.
+#[derive(Component, Debug)]
+pub struct AutoShiftDelayLabel;
+
+/// Text of the button showing the selected auto-repeat rate.
+#[derive(Component, Debug)]
+pub struct AutoRepeatRateLabel;
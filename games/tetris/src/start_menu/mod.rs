@@ -6,25 +6,36 @@ use crate::{
     utils::systems::{despawn_screen, vert_menu_controls},
 };
 mod components;
+mod resources;
 mod systems;
 
 pub struct StartMenu;
 
 impl Plugin for StartMenu {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::StartMenu), systems::render_screen)
-            .add_systems(
-                Update,
-                (
-                    vert_menu_controls,
-                    on_select_item,
-                    focused_button_decoration,
-                )
-                    .run_if(in_state(GameState::StartMenu)),
+        app.add_systems(
+            OnEnter(GameState::StartMenu),
+            (systems::render_screen, systems::reset_attract_timer),
+        )
+        .insert_resource(resources::AttractTimer::default())
+        .add_systems(
+            Update,
+            (
+                vert_menu_controls,
+                on_select_item,
+                systems::change_board,
+                systems::change_players,
+                systems::change_skin,
+                systems::change_auto_shift_delay,
+                systems::change_auto_repeat_rate,
+                focused_button_decoration,
+                systems::attract_mode,
             )
-            .add_systems(
-                OnExit(GameState::StartMenu),
-                despawn_screen::<components::OnMenuScreen>,
-            );
+                .run_if(in_state(GameState::StartMenu)),
+        )
+        .add_systems(
+            OnExit(GameState::StartMenu),
+            despawn_screen::<components::OnMenuScreen>,
+        );
     }
 }
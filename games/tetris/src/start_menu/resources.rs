@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+
+use crate::consts;
+
+/// Idle time in the start menu, before the demo game starts.
+#[derive(Debug, Clone, Resource, Deref, DerefMut)]
+pub struct AttractTimer(pub Timer);
+
+impl Default for AttractTimer {
+    fn default() -> Self {
+        Self(Timer::new(consts::ATTRACT_DELAY, TimerMode::Once))
+    }
+}
@@ -1,10 +1,28 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
-use crate::{consts, state::GameState, utils::components::HasFocus};
+use crate::{
+    consts,
+    engine::{BoardSize, GameMode, Replay},
+    skin::Skin,
+    state::{Demo, GameSettings, GameState, Playback},
+    utils::components::HasFocus,
+};
 
-use super::components::{ButtonAction, OnMenuScreen};
+use super::{
+    components::{
+        AutoRepeatRateLabel, AutoShiftDelayLabel, BoardLabel, ButtonAction, OnMenuScreen,
+        PlayersLabel, SkinLabel,
+    },
+    resources::AttractTimer,
+};
 
-pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn render_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<GameSettings>,
+) {
     let text_style = TextStyle {
         font_size: 16.,
         font: asset_server.load(cgc_lib::COMMON_FONT_NAME),
@@ -50,6 +68,28 @@ pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ..default()
                 })
                 .with_children(|parent| {
+                    for mode in GameMode::all() {
+                        let mut button = parent.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    border: UiRect::all(Val::Px(3.)),
+                                    padding: UiRect::all(Val::Percent(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ButtonAction::StartGame(mode),
+                        ));
+                        if mode == settings.mode {
+                            button.insert(HasFocus);
+                        }
+                        button.with_children(|parent| {
+                            parent.spawn(TextBundle {
+                                text: Text::from_section(mode.to_string(), text_style.clone()),
+                                ..default()
+                            });
+                        });
+                    }
                     parent
                         .spawn((
                             ButtonBundle {
@@ -60,12 +100,132 @@ pub fn render_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
                                 },
                                 ..default()
                             },
-                            HasFocus,
-                            ButtonAction::StartGame,
+                            ButtonAction::ChangeBoard,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        board_label(&settings.board),
+                                        text_style.clone(),
+                                    ),
+                                    ..default()
+                                },
+                                BoardLabel,
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    border: UiRect::all(Val::Px(3.)),
+                                    padding: UiRect::all(Val::Percent(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ButtonAction::ChangePlayers,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        players_label(settings.players),
+                                        text_style.clone(),
+                                    ),
+                                    ..default()
+                                },
+                                PlayersLabel,
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    border: UiRect::all(Val::Px(3.)),
+                                    padding: UiRect::all(Val::Percent(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ButtonAction::ChangeSkin,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        skin_label(settings.skin),
+                                        text_style.clone(),
+                                    ),
+                                    ..default()
+                                },
+                                SkinLabel,
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    border: UiRect::all(Val::Px(3.)),
+                                    padding: UiRect::all(Val::Percent(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ButtonAction::ChangeAutoShiftDelay,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        auto_shift_delay_label(settings.auto_shift_delay),
+                                        text_style.clone(),
+                                    ),
+                                    ..default()
+                                },
+                                AutoShiftDelayLabel,
+                            ));
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    border: UiRect::all(Val::Px(3.)),
+                                    padding: UiRect::all(Val::Percent(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ButtonAction::ChangeAutoRepeatRate,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        auto_repeat_rate_label(settings.auto_repeat_rate),
+                                        text_style.clone(),
+                                    ),
+                                    ..default()
+                                },
+                                AutoRepeatRateLabel,
+                            ));
+                        });
+                    #[cfg(not(target_arch = "wasm32"))]
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    border: UiRect::all(Val::Px(3.)),
+                                    padding: UiRect::all(Val::Percent(4.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ButtonAction::WatchReplay,
                         ))
                         .with_children(|parent| {
                             parent.spawn(TextBundle {
-                                text: Text::from_section("START", text_style.clone()),
+                                text: Text::from_section("REPLAY", text_style.clone()),
                                 ..default()
                             });
                         });
@@ -108,15 +268,218 @@ pub fn on_select_item(
     focued_button_query: Query<&ButtonAction, With<HasFocus>>,
     mut app_exit: EventWriter<AppExit>,
     mut app_state: ResMut<NextState<GameState>>,
+    mut playback: ResMut<Playback>,
+    mut settings: ResMut<GameSettings>,
 ) {
     if inputs.just_pressed(KeyCode::Enter) {
         match focued_button_query.single() {
             ButtonAction::Quit => {
                 app_exit.send(AppExit::Success);
             }
-            ButtonAction::StartGame => {
+            ButtonAction::StartGame(mode) => {
+                settings.mode = *mode;
                 app_state.set(GameState::Playing);
             }
+            // Handled by the `change_*` systems.
+            ButtonAction::ChangeBoard
+            | ButtonAction::ChangePlayers
+            | ButtonAction::ChangeSkin
+            | ButtonAction::ChangeAutoShiftDelay
+            | ButtonAction::ChangeAutoRepeatRate => {}
+            ButtonAction::WatchReplay => match load_replay() {
+                Ok(replay) => {
+                    **playback = Some(replay);
+                    app_state.set(GameState::Playing);
+                }
+                Err(err) => warn!("Failed to load the replay: {err}"),
+            },
+        }
+    }
+}
+
+fn board_label(size: &BoardSize) -> String {
+    format!("BOARD: {size}")
+}
+
+/// Switches to the next board size preset.
+pub fn change_board(
+    inputs: Res<ButtonInput<KeyCode>>,
+    focued_button_query: Query<&ButtonAction, With<HasFocus>>,
+    mut settings: ResMut<GameSettings>,
+    mut label_query: Query<&mut Text, With<BoardLabel>>,
+) {
+    if !inputs.just_pressed(KeyCode::Enter)
+        || !matches!(
+            focued_button_query.get_single(),
+            Ok(ButtonAction::ChangeBoard)
+        )
+    {
+        return;
+    }
+    let presets = BoardSize::presets();
+    let current = presets
+        .iter()
+        .position(|size| *size == settings.board)
+        .unwrap_or(0);
+    settings.board = presets[(current + 1) % presets.len()];
+    for mut label in label_query.iter_mut() {
+        if let Some(section) = label.sections.first_mut() {
+            section.value = board_label(&settings.board);
+        }
+    }
+}
+
+fn players_label(players: usize) -> String {
+    format!("PLAYERS: {players}")
+}
+
+/// Switches between a single player game and a local versus.
+pub fn change_players(
+    inputs: Res<ButtonInput<KeyCode>>,
+    focued_button_query: Query<&ButtonAction, With<HasFocus>>,
+    mut settings: ResMut<GameSettings>,
+    mut label_query: Query<&mut Text, With<PlayersLabel>>,
+) {
+    if !inputs.just_pressed(KeyCode::Enter)
+        || !matches!(
+            focued_button_query.get_single(),
+            Ok(ButtonAction::ChangePlayers)
+        )
+    {
+        return;
+    }
+    settings.players = settings.players % consts::MAX_PLAYERS + 1;
+    for mut label in label_query.iter_mut() {
+        if let Some(section) = label.sections.first_mut() {
+            section.value = players_label(settings.players);
+        }
+    }
+}
+
+fn skin_label(skin: Skin) -> String {
+    format!("SKIN: {skin}")
+}
+
+/// Switches to the next skin.
+pub fn change_skin(
+    inputs: Res<ButtonInput<KeyCode>>,
+    focued_button_query: Query<&ButtonAction, With<HasFocus>>,
+    mut settings: ResMut<GameSettings>,
+    mut label_query: Query<&mut Text, With<SkinLabel>>,
+) {
+    if !inputs.just_pressed(KeyCode::Enter)
+        || !matches!(
+            focued_button_query.get_single(),
+            Ok(ButtonAction::ChangeSkin)
+        )
+    {
+        return;
+    }
+    let skins = Skin::all();
+    let current = skins
+        .iter()
+        .position(|skin| *skin == settings.skin)
+        .unwrap_or(0);
+    settings.skin = skins[(current + 1) % skins.len()];
+    for mut label in label_query.iter_mut() {
+        if let Some(section) = label.sections.first_mut() {
+            section.value = skin_label(settings.skin);
+        }
+    }
+}
+
+/// Preset which follows the current one, the first one
+/// if the current value is not among the presets.
+fn next_duration(presets: &[Duration], current: Duration) -> Duration {
+    let next = presets
+        .iter()
+        .position(|preset| *preset == current)
+        .map_or(0, |index| index + 1);
+    presets[next % presets.len()]
+}
+
+fn auto_shift_delay_label(delay: Duration) -> String {
+    format!("DAS: {}MS", delay.as_millis())
+}
+
+/// Switches to the next auto-shift delay preset.
+pub fn change_auto_shift_delay(
+    inputs: Res<ButtonInput<KeyCode>>,
+    focued_button_query: Query<&ButtonAction, With<HasFocus>>,
+    mut settings: ResMut<GameSettings>,
+    mut label_query: Query<&mut Text, With<AutoShiftDelayLabel>>,
+) {
+    if !inputs.just_pressed(KeyCode::Enter)
+        || !matches!(
+            focued_button_query.get_single(),
+            Ok(ButtonAction::ChangeAutoShiftDelay)
+        )
+    {
+        return;
+    }
+    settings.auto_shift_delay =
+        next_duration(&consts::AUTO_SHIFT_DELAYS, settings.auto_shift_delay);
+    for mut label in label_query.iter_mut() {
+        if let Some(section) = label.sections.first_mut() {
+            section.value = auto_shift_delay_label(settings.auto_shift_delay);
+        }
+    }
+}
+
+fn auto_repeat_rate_label(rate: Duration) -> String {
+    format!("ARR: {}MS", rate.as_millis())
+}
+
+/// Switches to the next auto-repeat rate preset.
+pub fn change_auto_repeat_rate(
+    inputs: Res<ButtonInput<KeyCode>>,
+    focued_button_query: Query<&ButtonAction, With<HasFocus>>,
+    mut settings: ResMut<GameSettings>,
+    mut label_query: Query<&mut Text, With<AutoRepeatRateLabel>>,
+) {
+    if !inputs.just_pressed(KeyCode::Enter)
+        || !matches!(
+            focued_button_query.get_single(),
+            Ok(ButtonAction::ChangeAutoRepeatRate)
+        )
+    {
+        return;
+    }
+    settings.auto_repeat_rate =
+        next_duration(&consts::AUTO_REPEAT_RATES, settings.auto_repeat_rate);
+    for mut label in label_query.iter_mut() {
+        if let Some(section) = label.sections.first_mut() {
+            section.value = auto_repeat_rate_label(settings.auto_repeat_rate);
         }
     }
 }
+
+pub fn reset_attract_timer(mut timer: ResMut<AttractTimer>, mut demo: ResMut<Demo>) {
+    timer.reset();
+    **demo = false;
+}
+
+/// Starts a demo game played by the bot,
+/// when no key has been pressed for a while.
+pub fn attract_mode(
+    inputs: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut timer: ResMut<AttractTimer>,
+    mut demo: ResMut<Demo>,
+    mut app_state: ResMut<NextState<GameState>>,
+) {
+    if inputs.get_just_pressed().next().is_some() {
+        timer.reset();
+        return;
+    }
+    if timer.tick(time.delta()).just_finished() {
+        **demo = true;
+        app_state.set(GameState::Playing);
+    }
+}
+
+fn load_replay() -> Result<Replay, String> {
+    std::fs::read_to_string(consts::REPLAY_FILE)
+        .map_err(|err| err.to_string())?
+        .parse()
+}
@@ -1,11 +1,67 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Resource)]
-pub struct Score {
-    pub score: usize,
-    pub lines_cleared: usize,
+use crate::{
+    consts,
+    engine::{BoardSize, GameMode, Replay, TetrisEngine, TopOut},
+    skin::Skin,
+};
+
+/// Game of a single player, shared between the game screen
+/// and the game over screen.
+#[derive(Debug, Clone, Default, Component, Deref, DerefMut)]
+pub struct Game(pub TetrisEngine);
+
+/// Player entity, which owns a game along with its timers.
+/// Players are kept until the next game starts,
+/// so the game over screen can show their results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub struct Player {
+    pub index: usize,
+}
+
+/// Marks the player, whose game has ended by topping out.
+#[derive(Debug, Clone, Component)]
+pub struct ToppedOut {
+    pub reason: TopOut,
+}
+
+/// Game options chosen in the start menu.
+#[derive(Debug, Clone, Resource)]
+pub struct GameSettings {
+    pub mode: GameMode,
+    pub board: BoardSize,
+    pub players: usize,
+    pub skin: Skin,
+    // Delay before a held horizontal move starts repeating.
+    pub auto_shift_delay: Duration,
+    // Interval between repeated moves, zero moves to the wall at once.
+    pub auto_repeat_rate: Duration,
 }
 
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::default(),
+            board: BoardSize::default(),
+            players: 1,
+            skin: Skin::default(),
+            auto_shift_delay: consts::AUTO_SHIFT_DELAY,
+            auto_repeat_rate: consts::AUTO_REPEAT_RATE,
+        }
+    }
+}
+
+/// Demo game played by the bot, started from the idle start menu.
+/// Any key returns to the menu.
+#[derive(Debug, Clone, Default, Resource, Deref, DerefMut)]
+pub struct Demo(pub bool);
+
+/// Replay which is being played back instead of player inputs.
+#[derive(Debug, Clone, Default, Resource, Deref, DerefMut)]
+pub struct Playback(pub Option<Replay>);
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Resource)]
 pub struct Debug {
     pub enabled: bool,
@@ -18,3 +74,13 @@ pub enum GameState {
     Playing,
     GameOver,
 }
+
+/// Whether the game is running or paused.
+/// Board is kept while the game is paused.
+#[derive(SubStates, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[source(GameState = GameState::Playing)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}